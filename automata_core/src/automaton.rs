@@ -0,0 +1,50 @@
+/// The interface every engine exposes to the plugin and the visualiser.
+///
+/// Cells are read as values in `[0, 1]`, laid out row major, so anything
+/// that turns a board into a kernel or a picture can work with any engine.
+pub trait Automaton: Send {
+    /// Advances one generation.
+    fn step(&mut self);
+
+    fn width(&self) -> usize;
+
+    fn height(&self) -> usize;
+
+    /// Every cell, `width * height` of them, with cell `(x, y)` at
+    /// `y * width + x`.
+    fn cells(&self) -> &[f32];
+
+    fn cell(&self, x: usize, y: usize) -> f32 {
+        self.cells()[y * self.width() + x]
+    }
+
+    /// Empties the board.
+    fn reset(&mut self);
+
+    /// Replaces the board with the engine's random starting state, drawn
+    /// from `seeder::rng(seed)`, so the same seed gives the same board on
+    /// any machine.
+    fn seed(&mut self, seed: u64);
+
+    /// Replaces the board with the given live cells, dropping any that fall
+    /// outside it. Continuous engines fill them in, and one dimensional
+    /// engines start from the cells on the middle row.
+    fn load(&mut self, cells: &[(i64, i64)]);
+
+    /// Kills the given live cells and brings the given dead ones to life,
+    /// ignoring any outside the board. Continuous engines invert them, and
+    /// one dimensional engines flip the cells on the current row, `y = 0`.
+    fn flip(&mut self, cells: &[(i64, i64)]);
+
+    /// Everything needed to bring back the current generation with
+    /// `restore`, apart from settings like the rule.
+    fn save(&self) -> Vec<u8>;
+
+    /// Goes back to a generation from `save`. States saved with a
+    /// different board size are ignored.
+    fn restore(&mut self, state: &[u8]);
+
+    /// How many cells are alive. Continuous engines count the cells that are
+    /// at least half way to full.
+    fn population(&self) -> usize;
+}
@@ -0,0 +1,148 @@
+//! Runs a pattern or a random soup forward and saves the result.
+//!
+//! ```text
+//! automata-export [options] OUTPUT.{rle,pbm,pgm}
+//!     --pattern FILE      start from an RLE, .cells or Life 1.06 file
+//!     --seed N            seed for the random soup used without a pattern
+//!     --rule RULE         defaults to the pattern's rule, or B3/S23
+//!     --generations N     how far to run before saving, 0 by default
+//!     --size N            board size, and the size of exported images
+//!     --topology NAME     run on a bounded board with this topology instead
+//!                         of the unbounded Hashlife plane
+//! ```
+
+use std::{path::PathBuf, process::ExitCode};
+
+use automata_core::{
+    export::{self, Format},
+    hashlife::HashLife,
+    life::Life,
+    pattern::{Pattern, Rotation},
+    rule::Rule,
+    topology::Topology,
+    Automaton,
+};
+
+const SIZE: usize = 64;
+const SEED: u64 = 69;
+
+struct Options {
+    output: PathBuf,
+    pattern: Option<Pattern>,
+    seed: u64,
+    rule: Option<Rule>,
+    generations: u64,
+    size: usize,
+    topology: Option<Topology>,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match parse_args(&args).and_then(|options| run(&options)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        output: PathBuf::new(),
+        pattern: None,
+        seed: SEED,
+        rule: None,
+        generations: 0,
+        size: SIZE,
+        topology: None,
+    };
+    let mut output = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--pattern" => {
+                let path = value()?;
+                let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+                let pattern = Pattern::parse(&text).map_err(|e| format!("{path}: {e}"))?;
+                options.pattern = Some(pattern);
+            }
+            "--seed" => options.seed = parse_number(value()?)?,
+            "--rule" => {
+                let rule = value()?;
+                options.rule =
+                    Some(Rule::parse(rule).map_err(|e| format!("invalid rule {rule:?}: {e}"))?);
+            }
+            "--generations" => options.generations = parse_number(value()?)?,
+            "--size" => options.size = parse_number(value()?)?,
+            "--topology" => options.topology = Some(value()?.parse()?),
+            path if output.is_none() && !path.starts_with("--") => {
+                output = Some(PathBuf::from(path))
+            }
+            arg => return Err(format!("unexpected argument {arg:?}")),
+        }
+    }
+
+    options.output = output.ok_or("expected an output file")?;
+    Ok(options)
+}
+
+fn parse_number<T: std::str::FromStr>(s: &str) -> Result<T, String> {
+    s.parse()
+        .map_err(|_| format!("expected a number, found {s:?}"))
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let format =
+        Format::from_path(&options.output).ok_or("the output must be a .rle, .pbm or .pgm file")?;
+    let rule = options
+        .rule
+        .or(options.pattern.as_ref().and_then(|p| p.rule))
+        .unwrap_or_default();
+
+    // patterns start in the middle of the board
+    let place = |pattern: &Pattern| {
+        let x = (options.size as i64 - pattern.width as i64) / 2;
+        let y = (options.size as i64 - pattern.height as i64) / 2;
+        pattern
+            .placed(x, y, Rotation::None, false)
+            .collect::<Vec<_>>()
+    };
+
+    let (automaton, snapshot): (Box<dyn Automaton>, Pattern) = match options.topology {
+        Some(topology) => {
+            let mut life = Life::new(options.size);
+            life.set_rule(rule);
+            life.set_topology(topology);
+            match &options.pattern {
+                Some(pattern) => life.load(&place(pattern)),
+                None => life.seed(options.seed),
+            }
+            for _ in 0..options.generations {
+                life.step();
+            }
+            let snapshot = life.pattern();
+            (Box::new(life), snapshot)
+        }
+        None => {
+            let mut life = HashLife::new(options.size);
+            life.set_rule(rule);
+            match &options.pattern {
+                Some(pattern) => life.load(&place(pattern)),
+                None => life.seed(options.seed),
+            }
+            life.advance(options.generations);
+            let snapshot = life.pattern();
+            (Box::new(life), snapshot)
+        }
+    };
+
+    let bytes = match format {
+        Format::Rle => snapshot.to_rle().into_bytes(),
+        Format::Pbm => export::pbm(automaton.as_ref()),
+        Format::Pgm => export::pgm(automaton.as_ref()),
+    };
+    std::fs::write(&options.output, bytes).map_err(|e| format!("{}: {e}", options.output.display()))
+}
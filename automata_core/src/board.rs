@@ -0,0 +1,486 @@
+use std::sync::Arc;
+
+use crate::{neighborhood::Neighborhood, pool::Pool, rule::Rule, topology::Topology};
+
+/// The fewest rows worth handing to a worker, below which the threads cost
+/// more than they save.
+const MIN_STRIP: usize = 64;
+
+/// Whether a board of `size` is big enough to be stepped in strips, and so
+/// worth giving a `Pool`. A board as small as the plugin's 33 cell kernel
+/// steps a whole generation in less time than it takes to wake a worker.
+pub fn splits(size: usize) -> bool {
+    size >= 2 * MIN_STRIP
+}
+
+pub trait Board {
+    fn size(&self) -> usize;
+    fn topology(&self) -> Topology;
+    fn set_topology(&mut self, topology: Topology);
+    fn neighborhood(&self) -> &Neighborhood;
+    fn set_neighborhood(&mut self, neighborhood: Neighborhood);
+    fn get(&self, x: usize, y: usize) -> bool;
+    fn set(&mut self, x: usize, y: usize, alive: bool);
+    /// 0 for dead cells, 1 for live cells, and 2 upwards for the refractory
+    /// states of Generations rules.
+    fn state(&self, x: usize, y: usize) -> u8;
+    fn set_state(&mut self, x: usize, y: usize, state: u8);
+    fn clear(&mut self);
+    fn population(&self) -> usize;
+    fn step(&mut self, rule: &Rule);
+}
+
+/// A fixed size, double buffered board with one bit per cell.
+///
+/// Rows are stored with a one cell halo on every side so that neighbor
+/// counts can be computed 64 cells at a time without special casing the
+/// edges. Cell `(x, y)` lives at row `y + 1`, bit `x + 1`. The halo is
+/// rebuilt from the board's topology at the start of every step.
+///
+/// Any neighborhood other than `Moore(1)` falls back to summing weighted
+/// offsets one cell at a time.
+///
+/// With a `Pool`, `Moore(1)` boards of at least two strips of `MIN_STRIP`
+/// rows are cut into strips, one per worker and one for the calling thread.
+/// Each strip copies its rows, with the row above and below as a halo, into
+/// buffers kept from step to step, and runs the same code as the serial
+/// step, so the result is bit for bit the same.
+///
+/// Refractory states are kept to the side in `decay`, one byte per cell,
+/// with `refractory` marking the non-zero ones in the same layout as
+/// `cells` so births onto them can be masked out word by word.
+pub struct BitBoard {
+    size: usize,
+    topology: Topology,
+    neighborhood: Neighborhood,
+    offsets: [Vec<(i32, i32, u8)>; 2],
+    stride: usize,
+    interior: Vec<u64>,
+    cells: Vec<u64>,
+    next: Vec<u64>,
+    decay: Vec<u8>,
+    refractory: Vec<u64>,
+    pool: Option<Arc<Pool>>,
+    strips: Vec<Strip>,
+}
+
+/// One strip's copy of the board, handed to a worker and back every step.
+#[derive(Default)]
+struct Strip {
+    cells: Vec<u64>,
+    refractory: Vec<u64>,
+    interior: Vec<u64>,
+    next: Vec<u64>,
+}
+
+impl BitBoard {
+    pub fn new(size: usize) -> Self {
+        let stride = (size + 2).div_ceil(64);
+
+        let mut interior = vec![0; stride];
+        for bit in 1..=size {
+            interior[bit / 64] |= 1 << (bit % 64);
+        }
+
+        let neighborhood = Neighborhood::default();
+        let offsets = [neighborhood.offsets(0), neighborhood.offsets(1)];
+
+        Self {
+            size,
+            topology: Topology::default(),
+            neighborhood,
+            offsets,
+            stride,
+            interior,
+            cells: vec![0; stride * (size + 2)],
+            next: vec![0; stride * (size + 2)],
+            decay: vec![0; size * size],
+            refractory: vec![0; stride * (size + 2)],
+            pool: None,
+            strips: vec![],
+        }
+    }
+
+    /// Steps large boards on `pool`'s workers, or on the calling thread
+    /// with `None`.
+    pub fn set_pool(&mut self, pool: Option<Arc<Pool>>) {
+        self.pool = pool;
+    }
+
+    fn bit(&self, row: usize, bit: usize) -> bool {
+        self.cells[row * self.stride + bit / 64] & (1 << (bit % 64)) != 0
+    }
+
+    fn put(&mut self, row: usize, bit: usize, on: bool) {
+        put(&mut self.cells[row * self.stride + bit / 64], bit, on);
+    }
+
+    /// Moves live cells that just died into the first refractory state and
+    /// ages the ones already there. Must run before `next` is swapped in.
+    fn advance_decay(&mut self, rule: &Rule) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let word = (y + 1) * self.stride + (x + 1) / 64;
+                let mask = 1 << ((x + 1) % 64);
+                let was_alive = self.cells[word] & mask != 0;
+                let is_alive = self.next[word] & mask != 0;
+
+                let decay = &mut self.decay[y * self.size + x];
+                *decay = match *decay {
+                    0 if was_alive && !is_alive && rule.states() > 2 => 2,
+                    0 => 0,
+                    d => rule.decay(d),
+                };
+                put(&mut self.refractory[word], x + 1, *decay != 0);
+            }
+        }
+    }
+
+    fn step_moore(&mut self, rule: &Rule) {
+        self.refresh_halo();
+
+        let transitions = Transitions {
+            born: std::array::from_fn(|n| rule.born(n as u8)),
+            survives: std::array::from_fn(|n| rule.survives(n as u8)),
+        };
+
+        let stride = self.stride;
+        let strips = match &self.pool {
+            Some(pool) => (pool.threads() + 1).min(self.size / MIN_STRIP),
+            None => 1,
+        };
+        let pool = match &self.pool {
+            Some(pool) if strips > 1 => pool,
+            _ => {
+                transitions.step_rows(
+                    &self.cells,
+                    &self.refractory[stride..],
+                    &self.interior,
+                    &mut self.next[stride..(self.size + 1) * stride],
+                );
+                return;
+            }
+        };
+
+        // strip i starts at board row size * i / strips, one row down with the halo
+        let bounds = |i: usize| 1 + self.size * i / strips;
+        let mut scratch = std::mem::take(&mut self.strips);
+        scratch.resize_with(strips, Strip::default);
+        let tasks = scratch.into_iter().enumerate().map(|(i, mut strip)| {
+            let (start, end) = (bounds(i), bounds(i + 1));
+            refill(
+                &mut strip.cells,
+                &self.cells[(start - 1) * stride..(end + 1) * stride],
+            );
+            refill(
+                &mut strip.refractory,
+                &self.refractory[start * stride..end * stride],
+            );
+            refill(&mut strip.interior, &self.interior);
+            strip.next.resize((end - start) * stride, 0);
+            move || {
+                transitions.step_rows(
+                    &strip.cells,
+                    &strip.refractory,
+                    &strip.interior,
+                    &mut strip.next,
+                );
+                strip
+            }
+        });
+
+        let stepped = pool.map(tasks);
+        for (i, strip) in stepped.iter().enumerate() {
+            let start = bounds(i) * stride;
+            self.next[start..start + strip.next.len()].copy_from_slice(&strip.next);
+        }
+        self.strips = stepped;
+    }
+
+    fn step_offsets(&mut self, rule: &Rule) {
+        self.next.fill(0);
+
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let mut count = 0u32;
+                for (dx, dy, weight) in &self.offsets[y % 2] {
+                    let neighbor = self.topology.map(x as i32 + dx, y as i32 + dy, self.size);
+                    if let Some((nx, ny)) = neighbor {
+                        if self.get(nx, ny) {
+                            count += *weight as u32;
+                        }
+                    }
+                }
+
+                let count = count.min(u8::MAX as u32) as u8;
+                let alive = match self.state(x, y) {
+                    0 => rule.born(count),
+                    1 => rule.survives(count),
+                    _ => false,
+                };
+                if alive {
+                    let bit = x + 1;
+                    self.next[(y + 1) * self.stride + bit / 64] |= 1 << (bit % 64);
+                }
+            }
+        }
+    }
+
+    fn refresh_halo(&mut self) {
+        let n = self.size as i32;
+        for i in -1..=n {
+            for (x, y) in [(i, -1), (i, n), (-1, i), (n, i)] {
+                let on = match self.topology.map(x, y, self.size) {
+                    Some((x, y)) => self.get(x, y),
+                    None => false,
+                };
+                self.put((y + 1) as usize, (x + 1) as usize, on);
+            }
+        }
+    }
+}
+
+impl Board for BitBoard {
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    fn neighborhood(&self) -> &Neighborhood {
+        &self.neighborhood
+    }
+
+    fn set_neighborhood(&mut self, neighborhood: Neighborhood) {
+        self.offsets = [neighborhood.offsets(0), neighborhood.offsets(1)];
+        self.neighborhood = neighborhood;
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.bit(y + 1, x + 1)
+    }
+
+    fn set(&mut self, x: usize, y: usize, alive: bool) {
+        self.put(y + 1, x + 1, alive);
+        if self.decay[y * self.size + x] != 0 {
+            self.decay[y * self.size + x] = 0;
+            put(&mut self.refractory[(y + 1) * self.stride + (x + 1) / 64], x + 1, false);
+        }
+    }
+
+    fn state(&self, x: usize, y: usize) -> u8 {
+        match self.get(x, y) {
+            true => 1,
+            false => self.decay[y * self.size + x],
+        }
+    }
+
+    fn set_state(&mut self, x: usize, y: usize, state: u8) {
+        self.set(x, y, state == 1);
+        if state > 1 {
+            self.decay[y * self.size + x] = state;
+            put(
+                &mut self.refractory[(y + 1) * self.stride + (x + 1) / 64],
+                x + 1,
+                true,
+            );
+        }
+    }
+
+    fn clear(&mut self) {
+        self.cells.fill(0);
+        self.decay.fill(0);
+        self.refractory.fill(0);
+    }
+
+    fn population(&self) -> usize {
+        self.cells
+            .chunks(self.stride)
+            .skip(1)
+            .take(self.size)
+            .flat_map(|row| row.iter().zip(&self.interior))
+            .map(|(word, mask)| (word & mask).count_ones() as usize)
+            .sum()
+    }
+
+    fn step(&mut self, rule: &Rule) {
+        match self.neighborhood {
+            Neighborhood::Moore(1) => self.step_moore(rule),
+            _ => self.step_offsets(rule),
+        }
+
+        if rule.states() > 2 || self.refractory.iter().any(|word| *word != 0) {
+            self.advance_decay(rule);
+        }
+
+        std::mem::swap(&mut self.cells, &mut self.next);
+    }
+}
+
+/// What each neighbor count does to dead and live cells under one rule.
+#[derive(Clone, Copy)]
+struct Transitions {
+    born: [bool; 9],
+    survives: [bool; 9],
+}
+
+impl Transitions {
+    /// Steps the rows of `next` under the Moore neighborhood. `cells` holds
+    /// the same rows with one more above and below, and `refractory` starts
+    /// at the same row as `next`.
+    fn step_rows(&self, cells: &[u64], refractory: &[u64], interior: &[u64], next: &mut [u64]) {
+        let stride = interior.len();
+        for (row, out_row) in next.chunks_mut(stride).enumerate() {
+            let above = &cells[row * stride..(row + 1) * stride];
+            let middle = &cells[(row + 1) * stride..(row + 2) * stride];
+            let below = &cells[(row + 2) * stride..(row + 3) * stride];
+
+            for k in 0..stride {
+                let counts = count_neighbors([
+                    west(above, k),
+                    above[k],
+                    east(above, k),
+                    west(middle, k),
+                    east(middle, k),
+                    west(below, k),
+                    below[k],
+                    east(below, k),
+                ]);
+
+                let alive = middle[k];
+                let mut out = 0;
+                for (n, (born, survives)) in self.born.iter().zip(&self.survives).enumerate() {
+                    let matches = count_is(&counts, n as u8);
+                    if *born {
+                        out |= !alive & !refractory[row * stride + k] & matches;
+                    }
+                    if *survives {
+                        out |= alive & matches;
+                    }
+                }
+
+                out_row[k] = out & interior[k];
+            }
+        }
+    }
+}
+
+/// Replaces `buffer`'s contents, reusing its allocation.
+fn refill(buffer: &mut Vec<u64>, from: &[u64]) {
+    buffer.clear();
+    buffer.extend_from_slice(from);
+}
+
+fn put(word: &mut u64, bit: usize, on: bool) {
+    match on {
+        true => *word |= 1 << (bit % 64),
+        false => *word &= !(1 << (bit % 64)),
+    }
+}
+
+/// Each bit `i` of the result holds bit `i - 1` of the row.
+fn west(row: &[u64], k: usize) -> u64 {
+    let carry = match k {
+        0 => 0,
+        _ => row[k - 1] >> 63,
+    };
+    (row[k] << 1) | carry
+}
+
+/// Each bit `i` of the result holds bit `i + 1` of the row.
+fn east(row: &[u64], k: usize) -> u64 {
+    let carry = match row.get(k + 1) {
+        Some(next) => next << 63,
+        None => 0,
+    };
+    (row[k] >> 1) | carry
+}
+
+fn full_add(a: u64, b: u64, c: u64) -> (u64, u64) {
+    let t = a ^ b;
+    (t ^ c, (a & b) | (t & c))
+}
+
+fn half_add(a: u64, b: u64) -> (u64, u64) {
+    (a ^ b, a & b)
+}
+
+/// Bit sliced sum of eight one bit inputs, returned as four bit planes
+/// (ones, twos, fours, eights).
+fn count_neighbors(n: [u64; 8]) -> [u64; 4] {
+    let (s1, c1) = full_add(n[0], n[1], n[2]);
+    let (s2, c2) = full_add(n[3], n[4], n[5]);
+    let (s3, c3) = half_add(n[6], n[7]);
+    let (ones, c4) = full_add(s1, s2, s3);
+    let (t1, d1) = full_add(c1, c2, c3);
+    let (twos, d2) = half_add(t1, c4);
+    let (fours, eights) = half_add(d1, d2);
+
+    [ones, twos, fours, eights]
+}
+
+fn count_is(planes: &[u64; 4], n: u8) -> u64 {
+    planes
+        .iter()
+        .enumerate()
+        .fold(!0, |acc, (bit, plane)| match n & (1 << bit) {
+            0 => acc & !plane,
+            _ => acc & plane,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::seeder;
+
+    fn soup(size: usize, topology: Topology) -> BitBoard {
+        let mut rng = seeder::rng(size as u64);
+        let mut board = BitBoard::new(size);
+        board.set_topology(topology);
+        for y in 0..size {
+            for x in 0..size {
+                board.set(x, y, rng.gen_bool(0.35));
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn pooled_steps_match_serial_steps() {
+        let pool = Arc::new(Pool::new(3));
+        for size in [128, 200, 257] {
+            assert!(splits(size));
+            for topology in Topology::ALL {
+                for rule in [Rule::CONWAY, Rule::STAR_WARS] {
+                    let mut serial = soup(size, topology);
+                    let mut pooled = soup(size, topology);
+                    pooled.set_pool(Some(pool.clone()));
+
+                    for generation in 1..=8 {
+                        serial.step(&rule);
+                        pooled.step(&rule);
+                        for y in 0..size {
+                            for x in 0..size {
+                                assert_eq!(
+                                    serial.state(x, y),
+                                    pooled.state(x, y),
+                                    "{rule} on a {size} cell {topology} differs at ({x}, {y}) \
+                                     after {generation} generations",
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
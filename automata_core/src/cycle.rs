@@ -0,0 +1,99 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use crate::automaton::Automaton;
+
+/// What a board has settled into, if anything.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Status {
+    #[default]
+    Running,
+    /// Every cell is dead.
+    Extinct,
+    /// The board no longer changes.
+    Still,
+    /// The board repeats every this many generations.
+    Oscillating(usize),
+}
+
+impl Status {
+    /// Whether the board has stopped changing in any useful way.
+    pub fn is_stagnant(&self) -> bool {
+        *self != Status::Running
+    }
+}
+
+/// Spots boards that have died out or fallen into a cycle by remembering a
+/// hash of each recent generation.
+///
+/// Cells are hashed at 8 bits, so continuous engines that have all but
+/// stopped moving count as settled too. Only what the engine exposes
+/// through `cells` is hashed, which for Hashlife is the window: patterns
+/// that leave it are gone as far as the kernel is concerned.
+#[derive(Clone, Debug)]
+pub struct Detector {
+    hashes: VecDeque<u64>,
+    max_period: usize,
+}
+
+impl Detector {
+    /// Looks for cycles up to `max_period` generations long.
+    pub fn new(max_period: usize) -> Self {
+        Self {
+            hashes: VecDeque::with_capacity(max_period),
+            max_period: max_period.max(1),
+        }
+    }
+
+    /// Forgets the generations seen so far, for when the board is replaced.
+    pub fn clear(&mut self) {
+        self.hashes.clear();
+    }
+
+    /// Records the automaton's current generation and reports whether it
+    /// repeats one of the recent ones.
+    pub fn observe(&mut self, automaton: &dyn Automaton) -> Status {
+        let mut hasher = DefaultHasher::new();
+        let mut empty = true;
+        for cell in automaton.cells() {
+            let level = (cell.clamp(0.0, 1.0) * 255.0).round() as u8;
+            empty &= level == 0;
+            level.hash(&mut hasher);
+        }
+        let hash = hasher.finish();
+
+        // the most recent match is the shortest period
+        let period = self
+            .hashes
+            .iter()
+            .rev()
+            .position(|h| *h == hash)
+            .map(|i| i + 1);
+
+        if self.hashes.len() == self.max_period {
+            self.hashes.pop_front();
+        }
+        self.hashes.push_back(hash);
+
+        match (empty, period) {
+            (true, _) => Status::Extinct,
+            (false, Some(1)) => Status::Still,
+            (false, Some(period)) => Status::Oscillating(period),
+            (false, None) => Status::Running,
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Running => write!(f, "running"),
+            Status::Extinct => write!(f, "extinct"),
+            Status::Still => write!(f, "still life"),
+            Status::Oscillating(period) => write!(f, "period {period} oscillator"),
+        }
+    }
+}
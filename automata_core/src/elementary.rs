@@ -0,0 +1,214 @@
+use rand::Rng;
+
+use crate::{automaton::Automaton, seeder, topology::Topology};
+
+/// A radius one rule for a one dimensional automaton.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineRule {
+    /// Wolfram's numbering of the 256 two color rules: bit `4l + 2c + r` of
+    /// the number is the next state for the neighborhood `(l, c, r)`.
+    Elementary(u8),
+    /// A `colors` color rule that only looks at the sum of the three cells.
+    /// Digit `s` (in base `colors`) of `code` is the next state for sum `s`.
+    Totalistic { colors: u8, code: u64 },
+}
+
+impl LineRule {
+    pub fn colors(&self) -> u8 {
+        match self {
+            LineRule::Elementary(_) => 2,
+            LineRule::Totalistic { colors, .. } => *colors,
+        }
+    }
+
+    fn next(&self, left: u8, center: u8, right: u8) -> u8 {
+        match self {
+            LineRule::Elementary(rule) => (rule >> (4 * left + 2 * center + right)) & 1,
+            LineRule::Totalistic { colors, code } => {
+                let sum = (left + center + right) as u32;
+                ((code / (*colors as u64).pow(sum)) % *colors as u64) as u8
+            }
+        }
+    }
+}
+
+impl Default for LineRule {
+    fn default() -> Self {
+        LineRule::Elementary(30)
+    }
+}
+
+/// A one dimensional automaton that keeps the last `depth` rows around as
+/// its spacetime diagram. Age 0 is the current row, and is the top row of
+/// the cells it exposes as an `Automaton`.
+pub struct Elementary {
+    width: usize,
+    depth: usize,
+    topology: Topology,
+    rule: LineRule,
+    history: Vec<u8>,
+    head: usize,
+    field: Vec<f32>,
+}
+
+impl Elementary {
+    pub fn new(width: usize, depth: usize) -> Self {
+        Self {
+            width,
+            depth,
+            topology: Topology::default(),
+            rule: LineRule::default(),
+            history: vec![0; width * depth],
+            head: 0,
+            field: vec![0.0; width * depth],
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn set_rule(&mut self, rule: LineRule) {
+        if rule != self.rule {
+            self.rule = rule;
+            self.refresh_field();
+        }
+    }
+
+    /// Only the horizontal behavior of the topology matters for a line, so
+    /// the twisted topologies behave like the torus.
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    pub fn row(&self, age: usize) -> &[u8] {
+        let index = (self.head + self.depth - age % self.depth) % self.depth;
+        &self.history[index * self.width..(index + 1) * self.width]
+    }
+
+    pub fn state(&self, x: usize, age: usize) -> u8 {
+        self.row(age)[x]
+    }
+
+    pub fn clear(&mut self) {
+        self.history.fill(0);
+        self.head = 0;
+        self.field.fill(0.0);
+    }
+
+    /// Clears the history and starts from a single live cell in the middle.
+    pub fn seed_center(&mut self) {
+        self.clear();
+        let center = self.width / 2;
+        self.history[center] = 1;
+        self.refresh_field();
+    }
+
+    pub fn randomize<R: Rng>(&mut self, rng: &mut R) {
+        self.clear();
+        let colors = self.rule.colors();
+        for cell in &mut self.history[0..self.width] {
+            *cell = rng.gen_range(0..colors);
+        }
+        self.refresh_field();
+    }
+
+    fn refresh_field(&mut self) {
+        let scale = (self.rule.colors() - 1) as f32;
+        for age in 0..self.depth {
+            for x in 0..self.width {
+                let state = self.state(x, age);
+                self.field[age * self.width + x] = (state as f32 / scale).min(1.0);
+            }
+        }
+    }
+}
+
+impl Automaton for Elementary {
+    fn step(&mut self) {
+        let next = (self.head + 1) % self.depth;
+        let colors = self.rule.colors();
+
+        for x in 0..self.width {
+            let [left, center, right] = [-1, 0, 1].map(|dx| {
+                match self.topology.map(x as i32 + dx, 0, self.width) {
+                    // rows left over from a rule with more colors read as dead
+                    Some((nx, _)) => match self.history[self.head * self.width + nx] {
+                        state if state < colors => state,
+                        _ => 0,
+                    },
+                    None => 0,
+                }
+            });
+
+            self.history[next * self.width + x] = self.rule.next(left, center, right);
+        }
+
+        self.head = next;
+        self.refresh_field();
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.depth
+    }
+
+    fn cells(&self) -> &[f32] {
+        &self.field
+    }
+
+    fn reset(&mut self) {
+        self.clear();
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.randomize(&mut seeder::rng(seed));
+    }
+
+    fn load(&mut self, cells: &[(i64, i64)]) {
+        self.clear();
+        let (width, middle) = (self.width as i64, (self.depth / 2) as i64);
+        for &(x, y) in cells {
+            if y == middle && (0..width).contains(&x) {
+                self.history[x as usize] = 1;
+            }
+        }
+        self.refresh_field();
+    }
+
+    fn flip(&mut self, cells: &[(i64, i64)]) {
+        let width = self.width as i64;
+        for &(x, y) in cells {
+            if y == 0 && (0..width).contains(&x) {
+                let cell = &mut self.history[self.head * self.width + x as usize];
+                *cell = (*cell == 0) as u8;
+            }
+        }
+        self.refresh_field();
+    }
+
+    /// The whole spacetime diagram, oldest row first.
+    fn save(&self) -> Vec<u8> {
+        (0..self.depth)
+            .rev()
+            .flat_map(|age| self.row(age).iter().copied())
+            .collect()
+    }
+
+    fn restore(&mut self, state: &[u8]) {
+        if state.len() != self.history.len() {
+            return;
+        }
+
+        self.history.copy_from_slice(state);
+        self.head = self.depth - 1;
+        self.refresh_field();
+    }
+
+    fn population(&self) -> usize {
+        self.row(0).iter().filter(|state| **state != 0).count()
+    }
+}
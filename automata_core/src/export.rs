@@ -0,0 +1,65 @@
+use std::path::Path;
+
+use crate::automaton::Automaton;
+
+/// The formats a board can be saved in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Run length encoded live cells with the rule, readable by most Life
+    /// software and by `Pattern::parse`.
+    Rle,
+    /// A one bit image of the whole board.
+    Pbm,
+    /// A greyscale image of the whole board, keeping refractory states and
+    /// the continuous engines' in-between values.
+    Pgm,
+}
+
+impl Format {
+    /// Picks the format from a file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "rle" => Some(Format::Rle),
+            "pbm" => Some(Format::Pbm),
+            "pgm" => Some(Format::Pgm),
+            _ => None,
+        }
+    }
+}
+
+/// A binary PBM of every cell, with cells at least half way to full drawn
+/// black as the format expects.
+pub fn pbm(automaton: &dyn Automaton) -> Vec<u8> {
+    let (width, height) = (automaton.width(), automaton.height());
+    let mut out = format!("P4\n{width} {height}\n").into_bytes();
+
+    // rows are padded to whole bytes, most significant bit first
+    for y in 0..height {
+        for chunk in 0..width.div_ceil(8) {
+            let mut byte = 0;
+            for bit in 0..8 {
+                let x = chunk * 8 + bit;
+                if x < width && automaton.cell(x, y) >= 0.5 {
+                    byte |= 0x80 >> bit;
+                }
+            }
+            out.push(byte);
+        }
+    }
+    out
+}
+
+/// A binary PGM of every cell, darker the more alive it is to match `pbm`.
+pub fn pgm(automaton: &dyn Automaton) -> Vec<u8> {
+    let (width, height) = (automaton.width(), automaton.height());
+    let mut out = format!("P5\n{width} {height}\n255\n").into_bytes();
+
+    out.extend(
+        automaton
+            .cells()
+            .iter()
+            .map(|cell| ((1.0 - cell.clamp(0.0, 1.0)) * 255.0).round() as u8),
+    );
+    out
+}
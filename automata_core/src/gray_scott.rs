@@ -0,0 +1,256 @@
+use rand::Rng;
+
+use crate::{automaton::Automaton, seeder, topology::Topology};
+
+/// Laplacian stencil `(dx, dy, weight)`, the center taking `-1`.
+const STENCIL: [(i32, i32, f32); 8] = [
+    (-1, -1, 0.05),
+    (0, -1, 0.2),
+    (1, -1, 0.05),
+    (-1, 0, 0.2),
+    (1, 0, 0.2),
+    (-1, 1, 0.05),
+    (0, 1, 0.2),
+    (1, 1, 0.05),
+];
+
+/// Well known `(feed, kill)` regimes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Regime {
+    Spots,
+    Stripes,
+    Mitosis,
+    Coral,
+}
+
+impl Regime {
+    pub fn feed_kill(&self) -> (f32, f32) {
+        match self {
+            Regime::Spots => (0.03, 0.062),
+            Regime::Stripes => (0.029, 0.057),
+            Regime::Mitosis => (0.0367, 0.0649),
+            Regime::Coral => (0.0545, 0.062),
+        }
+    }
+}
+
+/// A two chemical Gray-Scott reaction-diffusion system.
+///
+/// `U` is fed in everywhere at the `feed` rate, `V` is removed at
+/// `feed + kill`, and wherever they meet `U + 2V -> 3V`. Both chemicals
+/// diffuse at their own rate. Edges that fall off a dead topology act as
+/// a reservoir of pure `U`.
+///
+/// The cells it exposes map both concentrations onto `[0, 1]`: 0 where the
+/// board is pure `U`, rising as `V` takes over.
+pub struct GrayScott {
+    size: usize,
+    topology: Topology,
+    feed: f32,
+    kill: f32,
+    diffuse_u: f32,
+    diffuse_v: f32,
+    iterations: usize,
+    u: Vec<f32>,
+    v: Vec<f32>,
+    next_u: Vec<f32>,
+    next_v: Vec<f32>,
+    field: Vec<f32>,
+}
+
+impl GrayScott {
+    pub fn new(size: usize) -> Self {
+        let (feed, kill) = Regime::Spots.feed_kill();
+        Self {
+            size,
+            topology: Topology::default(),
+            feed,
+            kill,
+            diffuse_u: 1.0,
+            diffuse_v: 0.5,
+            iterations: 16,
+            u: vec![1.0; size * size],
+            v: vec![0.0; size * size],
+            next_u: vec![1.0; size * size],
+            next_v: vec![0.0; size * size],
+            field: vec![0.0; size * size],
+        }
+    }
+
+    pub fn u(&self, x: usize, y: usize) -> f32 {
+        self.u[y * self.size + x]
+    }
+
+    pub fn v(&self, x: usize, y: usize) -> f32 {
+        self.v[y * self.size + x]
+    }
+
+    pub fn clear(&mut self) {
+        self.u.fill(1.0);
+        self.v.fill(0.0);
+        self.field.fill(0.0);
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    pub fn set_rates(&mut self, feed: f32, kill: f32, diffuse_u: f32, diffuse_v: f32) {
+        self.feed = feed;
+        self.kill = kill;
+        self.diffuse_u = diffuse_u;
+        self.diffuse_v = diffuse_v;
+    }
+
+    /// How many reaction-diffusion iterations make up one `step`.
+    pub fn set_iterations(&mut self, iterations: usize) {
+        self.iterations = iterations;
+    }
+
+    /// Drops a handful of `V` squares onto an otherwise pure `U` board.
+    pub fn randomize<R: Rng>(&mut self, rng: &mut R) {
+        self.clear();
+
+        let n = self.size;
+        let half = (n / 8).max(1);
+        for _ in 0..(n / 8).max(1) {
+            let (cx, cy) = (
+                rng.gen_range(0..n as u32) as usize,
+                rng.gen_range(0..n as u32) as usize,
+            );
+            for dy in 0..2 * half {
+                for dx in 0..2 * half {
+                    let (x, y) = ((cx + dx) % n, (cy + dy) % n);
+                    self.u[y * n + x] = 0.5 + rng.gen_range(-0.05..0.05);
+                    self.v[y * n + x] = 0.25 + rng.gen_range(-0.05..0.05);
+                }
+            }
+        }
+
+        self.refresh_field();
+    }
+
+    fn refresh_field(&mut self) {
+        for ((cell, u), v) in self.field.iter_mut().zip(&self.u).zip(&self.v) {
+            *cell = ((1.0 + v - u) / 2.0).clamp(0.0, 1.0);
+        }
+    }
+
+    fn iterate(&mut self) {
+        let n = self.size;
+        for y in 0..n {
+            for x in 0..n {
+                let i = y * n + x;
+                let (u, v) = (self.u[i], self.v[i]);
+
+                let (mut lap_u, mut lap_v) = (-u, -v);
+                for (dx, dy, weight) in STENCIL {
+                    let (nu, nv) = match self.topology.map(x as i32 + dx, y as i32 + dy, n) {
+                        Some((nx, ny)) => (self.u[ny * n + nx], self.v[ny * n + nx]),
+                        None => (1.0, 0.0),
+                    };
+                    lap_u += weight * nu;
+                    lap_v += weight * nv;
+                }
+
+                let reaction = u * v * v;
+                self.next_u[i] =
+                    (u + self.diffuse_u * lap_u - reaction + self.feed * (1.0 - u)).clamp(0.0, 1.0);
+                self.next_v[i] = (v + self.diffuse_v * lap_v + reaction
+                    - (self.feed + self.kill) * v)
+                    .clamp(0.0, 1.0);
+            }
+        }
+
+        std::mem::swap(&mut self.u, &mut self.next_u);
+        std::mem::swap(&mut self.v, &mut self.next_v);
+    }
+}
+
+impl Automaton for GrayScott {
+    fn step(&mut self) {
+        for _ in 0..self.iterations {
+            self.iterate();
+        }
+        self.refresh_field();
+    }
+
+    fn width(&self) -> usize {
+        self.size
+    }
+
+    fn height(&self) -> usize {
+        self.size
+    }
+
+    fn cells(&self) -> &[f32] {
+        &self.field
+    }
+
+    fn reset(&mut self) {
+        self.clear();
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.randomize(&mut seeder::rng(seed));
+    }
+
+    /// Live cells become `V` at the same mix `randomize` drops in.
+    fn load(&mut self, cells: &[(i64, i64)]) {
+        self.clear();
+        let size = self.size as i64;
+        for &(x, y) in cells {
+            if (0..size).contains(&x) && (0..size).contains(&y) {
+                let i = (y * size + x) as usize;
+                self.u[i] = 0.5;
+                self.v[i] = 0.25;
+            }
+        }
+        self.refresh_field();
+    }
+
+    /// Cells that are mostly `V` go back to pure `U`, and the rest get the
+    /// `load` mix.
+    fn flip(&mut self, cells: &[(i64, i64)]) {
+        let size = self.size as i64;
+        for &(x, y) in cells {
+            if (0..size).contains(&x) && (0..size).contains(&y) {
+                let i = (y * size + x) as usize;
+                let (u, v) = match self.field[i] >= 0.5 {
+                    true => (1.0, 0.0),
+                    false => (0.5, 0.25),
+                };
+                self.u[i] = u;
+                self.v[i] = v;
+            }
+        }
+        self.refresh_field();
+    }
+
+    /// Every `U` and then every `V`.
+    fn save(&self) -> Vec<u8> {
+        self.u
+            .iter()
+            .chain(&self.v)
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    fn restore(&mut self, state: &[u8]) {
+        if state.len() != (self.u.len() + self.v.len()) * 4 {
+            return;
+        }
+
+        let values = state
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()));
+        for (value, restored) in self.u.iter_mut().chain(&mut self.v).zip(values) {
+            *value = restored;
+        }
+        self.refresh_field();
+    }
+
+    fn population(&self) -> usize {
+        self.field.iter().filter(|cell| **cell >= 0.5).count()
+    }
+}
@@ -0,0 +1,579 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+
+use crate::{automaton::Automaton, pattern::Pattern, rule::Rule, seeder};
+
+/// Past this many nodes the arena is rebuilt from the live tree, dropping
+/// everything that is only reachable through the result cache. Tests
+/// collect far sooner, so that they cross a collection.
+const MAX_NODES: usize = match cfg!(test) {
+    true => 1 << 8,
+    false => 1 << 22,
+};
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+#[derive(Clone, Copy)]
+struct Node {
+    level: u8,
+    population: u64,
+    // nw, ne, sw, se; unused for single cells
+    children: [NodeId; 4],
+}
+
+/// A Life-like rule on an unbounded plane, stepped with Hashlife.
+///
+/// The plane is a quadtree of hash consed nodes, so identical regions are
+/// stored once, and the result of advancing each node is memoised. That
+/// lets `advance` move huge numbers of generations in time roughly
+/// logarithmic in the count for patterns with any regularity.
+///
+/// Only the birth and survival counts of the rule are used: the plane has
+/// no edges, always uses the Moore neighborhood, ignores refractory states,
+/// and treats B0 as absent. The automaton's cells are a `size` by `size`
+/// window centered on the origin.
+pub struct HashLife {
+    size: usize,
+    rule: Rule,
+    nodes: Vec<Node>,
+    interned: HashMap<[NodeId; 4], NodeId>,
+    results: HashMap<(NodeId, u8), NodeId>,
+    empty: Vec<NodeId>,
+    root: NodeId,
+    generation: u64,
+    field: Vec<f32>,
+}
+
+impl HashLife {
+    pub fn new(size: usize) -> Self {
+        let mut life = Self {
+            size,
+            rule: Rule::default(),
+            nodes: vec![],
+            interned: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![],
+            root: DEAD,
+            generation: 0,
+            field: vec![0.0; size * size],
+        };
+        life.reset();
+        life
+    }
+
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    /// Sets the rule, throwing away every memoised result if it changed.
+    pub fn set_rule(&mut self, rule: Rule) {
+        if rule != self.rule {
+            self.rule = rule;
+            self.results.clear();
+        }
+    }
+
+    /// Generations advanced since the last reset or seed.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Sets a cell relative to the top left corner of the window.
+    pub fn set(&mut self, x: usize, y: usize, alive: bool) {
+        let (x, y) = self.to_plane(x, y);
+        self.set_plane(x, y, alive);
+        self.refresh_field();
+    }
+
+    /// Brings the given cells to life, relative to the top left corner of
+    /// the window. Cells outside the window are kept.
+    pub fn stamp(&mut self, cells: impl IntoIterator<Item = (i64, i64)>) {
+        let half = (self.size / 2) as i64;
+        for (x, y) in cells {
+            self.set_plane(x - half, y - half, true);
+        }
+
+        self.refresh_field();
+    }
+
+    /// Every live cell on the plane and the rule, cropped to their bounding
+    /// box.
+    pub fn pattern(&self) -> Pattern {
+        Pattern::from_cells(None, Some(self.rule), &self.live_cells())
+    }
+
+    pub fn randomize<R: Rng>(&mut self, rng: &mut R) {
+        self.reset();
+
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if rng.gen() {
+                    let (x, y) = self.to_plane(x, y);
+                    self.set_plane(x, y, true);
+                }
+            }
+        }
+
+        self.refresh_field();
+    }
+
+    /// Advances any number of generations, one power of two at a time.
+    /// The arena is collected between the steps as well as before them, so
+    /// one long jump can't run far past `MAX_NODES`.
+    pub fn advance(&mut self, generations: u64) {
+        for k in 0..u64::BITS as u8 {
+            if generations & (1 << k) != 0 {
+                if self.nodes.len() > MAX_NODES {
+                    self.collect();
+                }
+                self.advance_pow2(k);
+            }
+        }
+
+        self.generation = self.generation.wrapping_add(generations);
+        self.refresh_field();
+    }
+
+    fn advance_pow2(&mut self, k: u8) {
+        // Grow until the pattern sits inside the middle quarter and the root
+        // is big enough to step 2^k at once, so nothing can escape the part
+        // of the plane the result covers.
+        loop {
+            let level = self.nodes[self.root as usize].level;
+            if level >= k + 3 {
+                let inner = self.centre(self.root);
+                let inner = self.centre(inner);
+                if self.count(inner) == self.count(self.root) {
+                    break;
+                }
+            }
+            self.expand();
+        }
+
+        self.root = self.result(self.root, k);
+    }
+
+    /// Every live cell on the plane, in plane coordinates.
+    fn live_cells(&self) -> Vec<(i64, i64)> {
+        let mut cells = vec![];
+        let half = self.half();
+        let mut stack = vec![(self.root, -half, -half)];
+        while let Some((id, x, y)) = stack.pop() {
+            let node = self.nodes[id as usize];
+            if node.population == 0 {
+                continue;
+            }
+            if node.level == 0 {
+                cells.push((x, y));
+                continue;
+            }
+
+            let quarter = 1 << (node.level - 1);
+            for (i, &child) in node.children.iter().enumerate() {
+                let (dx, dy) = ((i % 2) as i64 * quarter, (i / 2) as i64 * quarter);
+                stack.push((child, x + dx, y + dy));
+            }
+        }
+
+        cells
+    }
+
+    fn to_plane(&self, x: usize, y: usize) -> (i64, i64) {
+        let half = (self.size / 2) as i64;
+        (x as i64 - half, y as i64 - half)
+    }
+
+    fn count(&self, id: NodeId) -> u64 {
+        self.nodes[id as usize].population
+    }
+
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.interned.get(&children) {
+            return id;
+        }
+
+        let level = self.nodes[children[0] as usize].level + 1;
+        let population = children.iter().map(|&c| self.count(c)).sum();
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            level,
+            population,
+            children,
+        });
+        self.interned.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let below = *self.empty.last().unwrap();
+            let id = self.join([below; 4]);
+            self.empty.push(id);
+        }
+        self.empty[level as usize]
+    }
+
+    fn children(&self, id: NodeId) -> [NodeId; 4] {
+        self.nodes[id as usize].children
+    }
+
+    /// The middle half of a node, one level down.
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(id);
+        self.join([
+            self.children(nw)[3],
+            self.children(ne)[2],
+            self.children(sw)[1],
+            self.children(se)[0],
+        ])
+    }
+
+    /// Doubles the root around the origin with an empty border.
+    fn expand(&mut self) {
+        let level = self.nodes[self.root as usize].level;
+        let empty = self.empty(level - 1);
+        let [nw, ne, sw, se] = self.children(self.root);
+
+        let nw = self.join([empty, empty, empty, nw]);
+        let ne = self.join([empty, empty, ne, empty]);
+        let sw = self.join([empty, sw, empty, empty]);
+        let se = self.join([se, empty, empty, empty]);
+        self.root = self.join([nw, ne, sw, se]);
+    }
+
+    /// The middle half of a node of level `n`, advanced `2^k` generations,
+    /// where `k <= n - 2`.
+    fn result(&mut self, id: NodeId, k: u8) -> NodeId {
+        if let Some(&result) = self.results.get(&(id, k)) {
+            return result;
+        }
+
+        let level = self.nodes[id as usize].level;
+        let result = if self.count(id) == 0 {
+            self.empty(level - 1)
+        } else if level == 2 {
+            self.step_leaf(id)
+        } else {
+            let [nw, ne, sw, se] = self.children(id);
+            let [_, nw_ne, nw_sw, nw_se] = self.children(nw);
+            let [ne_nw, _, ne_sw, ne_se] = self.children(ne);
+            let [sw_nw, sw_ne, _, sw_se] = self.children(sw);
+            let [se_nw, se_ne, se_sw, _] = self.children(se);
+
+            // nine overlapping squares a level down, covering the node
+            let north = self.join([nw_ne, ne_nw, nw_se, ne_sw]);
+            let west = self.join([nw_sw, nw_se, sw_nw, sw_ne]);
+            let middle = self.join([nw_se, ne_sw, sw_ne, se_nw]);
+            let east = self.join([ne_sw, ne_se, se_nw, se_ne]);
+            let south = self.join([sw_ne, se_nw, sw_se, se_sw]);
+            let squares = [nw, north, ne, west, middle, east, sw, south, se];
+
+            let first = k.min(level - 3);
+            let r = squares.map(|square| self.result(square, first));
+
+            let quads = [
+                self.join([r[0], r[1], r[3], r[4]]),
+                self.join([r[1], r[2], r[4], r[5]]),
+                self.join([r[3], r[4], r[6], r[7]]),
+                self.join([r[4], r[5], r[7], r[8]]),
+            ];
+
+            // the first half of a full speed step is already done, otherwise
+            // the nine squares have moved all the way
+            let quads = match k == level - 2 {
+                true => quads.map(|quad| self.result(quad, level - 3)),
+                false => quads.map(|quad| self.centre(quad)),
+            };
+            self.join(quads)
+        };
+
+        self.results.insert((id, k), result);
+        result
+    }
+
+    /// One generation of the middle 2x2 of a 4x4 node.
+    fn step_leaf(&mut self, id: NodeId) -> NodeId {
+        let mut grid = [[false; 4]; 4];
+        for (q, &quad) in self.children(id).iter().enumerate() {
+            for (c, &cell) in self.children(quad).iter().enumerate() {
+                grid[(q / 2) * 2 + c / 2][(q % 2) * 2 + c % 2] = cell == ALIVE;
+            }
+        }
+
+        let mut next = [DEAD; 4];
+        for (i, cell) in next.iter_mut().enumerate() {
+            let (y, x) = (1 + i / 2, 1 + i % 2);
+            let mut count = 0;
+            for row in &grid[y - 1..=y + 1] {
+                count += row[x - 1..=x + 1].iter().filter(|&&c| c).count() as u8;
+            }
+            count -= grid[y][x] as u8;
+
+            let alive = match grid[y][x] {
+                true => self.rule.survives(count),
+                false => count > 0 && self.rule.born(count),
+            };
+            *cell = alive as NodeId;
+        }
+
+        self.join(next)
+    }
+
+    /// The root covers `[-2^(level-1), 2^(level-1))` on both axes.
+    fn half(&self) -> i64 {
+        1 << (self.nodes[self.root as usize].level - 1)
+    }
+
+    fn get_plane(&self, x: i64, y: i64) -> bool {
+        let half = self.half();
+        if x < -half || x >= half || y < -half || y >= half {
+            return false;
+        }
+
+        let (mut x, mut y) = ((x + half) as u64, (y + half) as u64);
+        let mut id = self.root;
+        while self.nodes[id as usize].level > 0 {
+            if self.count(id) == 0 {
+                return false;
+            }
+            let bit = self.nodes[id as usize].level - 1;
+            let quadrant = ((y >> bit) & 1) * 2 + ((x >> bit) & 1);
+            x &= !(1 << bit);
+            y &= !(1 << bit);
+            id = self.children(id)[quadrant as usize];
+        }
+        id == ALIVE
+    }
+
+    fn set_plane(&mut self, x: i64, y: i64, alive: bool) {
+        let mut half = self.half();
+        while x < -half || x >= half || y < -half || y >= half {
+            self.expand();
+            half = self.half();
+        }
+
+        self.root = self.set_node(self.root, (x + half) as u64, (y + half) as u64, alive);
+    }
+
+    fn set_node(&mut self, id: NodeId, x: u64, y: u64, alive: bool) -> NodeId {
+        let level = self.nodes[id as usize].level;
+        if level == 0 {
+            return alive as NodeId;
+        }
+
+        let bit = level - 1;
+        let quadrant = (((y >> bit) & 1) * 2 + ((x >> bit) & 1)) as usize;
+        let mut children = self.children(id);
+        children[quadrant] =
+            self.set_node(children[quadrant], x & !(1 << bit), y & !(1 << bit), alive);
+        self.join(children)
+    }
+
+    /// Rebuilds the arena with only the nodes reachable from the root.
+    fn collect(&mut self) {
+        let old = std::mem::take(&mut self.nodes);
+        self.interned.clear();
+        self.results.clear();
+        self.empty.clear();
+        self.push_cells();
+
+        let mut copied = HashMap::new();
+        self.root = self.copy(&old, self.root, &mut copied);
+    }
+
+    fn copy(&mut self, old: &[Node], id: NodeId, copied: &mut HashMap<NodeId, NodeId>) -> NodeId {
+        if old[id as usize].level == 0 {
+            return id;
+        }
+        if let Some(&new) = copied.get(&id) {
+            return new;
+        }
+
+        let children = old[id as usize]
+            .children
+            .map(|child| self.copy(old, child, copied));
+        let new = self.join(children);
+        copied.insert(id, new);
+        new
+    }
+
+    fn push_cells(&mut self) {
+        for population in [0, 1] {
+            self.nodes.push(Node {
+                level: 0,
+                population,
+                children: [DEAD; 4],
+            });
+        }
+        self.empty.push(DEAD);
+    }
+
+    fn refresh_field(&mut self) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let (px, py) = self.to_plane(x, y);
+                self.field[y * self.size + x] = self.get_plane(px, py) as u8 as f32;
+            }
+        }
+    }
+}
+
+impl Automaton for HashLife {
+    fn step(&mut self) {
+        self.advance(1);
+    }
+
+    fn width(&self) -> usize {
+        self.size
+    }
+
+    fn height(&self) -> usize {
+        self.size
+    }
+
+    fn cells(&self) -> &[f32] {
+        &self.field
+    }
+
+    fn reset(&mut self) {
+        self.nodes.clear();
+        self.interned.clear();
+        self.results.clear();
+        self.empty.clear();
+        self.push_cells();
+
+        self.root = self.empty(3);
+        self.generation = 0;
+        self.field.fill(0.0);
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.randomize(&mut seeder::rng(seed));
+    }
+
+    /// Keeps cells outside the window, like `stamp`.
+    fn load(&mut self, cells: &[(i64, i64)]) {
+        self.reset();
+        self.stamp(cells.iter().copied());
+    }
+
+    fn flip(&mut self, cells: &[(i64, i64)]) {
+        let size = self.size as i64;
+        for &(x, y) in cells {
+            if (0..size).contains(&x) && (0..size).contains(&y) {
+                let (x, y) = self.to_plane(x as usize, y as usize);
+                self.set_plane(x, y, !self.get_plane(x, y));
+            }
+        }
+
+        self.refresh_field();
+    }
+
+    /// The window size, the generation, and then every live cell on the
+    /// plane.
+    fn save(&self) -> Vec<u8> {
+        let mut out = vec![];
+        out.extend((self.size as u64).to_le_bytes());
+        out.extend(self.generation.to_le_bytes());
+        for (x, y) in self.live_cells() {
+            out.extend(x.to_le_bytes());
+            out.extend(y.to_le_bytes());
+        }
+        out
+    }
+
+    fn restore(&mut self, state: &[u8]) {
+        let word = |i: usize| u64::from_le_bytes(state[i * 8..(i + 1) * 8].try_into().unwrap());
+        if state.len() < 16 || !state.len().is_multiple_of(16) || word(0) != self.size as u64 {
+            return;
+        }
+
+        self.reset();
+        self.generation = word(1);
+        for cell in (2..state.len() / 8).step_by(2) {
+            self.set_plane(word(cell) as i64, word(cell + 1) as i64, true);
+        }
+        self.refresh_field();
+    }
+
+    /// Counts the whole plane, not just the window.
+    fn population(&self) -> usize {
+        self.count(self.root) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::{life::Life, topology::Topology};
+
+    #[test]
+    fn matches_life_on_a_dead_plane() {
+        // wide enough that nothing reaches the edge of the dead board in
+        // the generations run
+        let size = 8 + 2 * 120;
+        let middle = (size / 2) as i64;
+        let mut rng = seeder::rng(8);
+        let soup: Vec<_> = (0..8)
+            .flat_map(|y| (0..8).map(move |x| (middle - 4 + x, middle - 4 + y)))
+            .filter(|_| rng.gen())
+            .collect();
+
+        let mut life = Life::new(size);
+        life.set_topology(Topology::Dead);
+        life.load(&soup);
+        let mut hashlife = HashLife::new(size);
+        hashlife.load(&soup);
+
+        let mut collected = false;
+        for generations in [10, 50, 60] {
+            for _ in 0..generations {
+                life.step();
+            }
+            collected |= hashlife.nodes.len() > MAX_NODES;
+            hashlife.advance(generations);
+            assert_eq!(
+                hashlife.cells(),
+                life.cells(),
+                "after {} generations",
+                hashlife.generation()
+            );
+        }
+        assert!(collected, "never crossed a collection");
+    }
+
+    #[test]
+    fn collects_during_a_jump() {
+        let size = 8 + 2 * 130;
+        let middle = (size / 2) as i64;
+        let mut rng = seeder::rng(3);
+        let soup: Vec<_> = (0..8)
+            .flat_map(|y| (0..8).map(move |x| (middle - 4 + x, middle - 4 + y)))
+            .filter(|_| rng.gen())
+            .collect();
+
+        let mut life = Life::new(size);
+        life.set_topology(Topology::Dead);
+        life.load(&soup);
+        let mut hashlife = HashLife::new(size);
+        hashlife.load(&soup);
+
+        for _ in 0..127 {
+            life.step();
+        }
+        // seven steps in one jump, with only the last left uncollected
+        hashlife.advance(127);
+        assert!(
+            hashlife.nodes.len() <= MAX_NODES,
+            "{} nodes after the jump",
+            hashlife.nodes.len()
+        );
+        assert_eq!(hashlife.cells(), life.cells());
+    }
+}
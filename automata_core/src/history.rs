@@ -0,0 +1,359 @@
+use std::collections::VecDeque;
+
+/// How many generations apart whole states are kept, which bounds how many
+/// deltas `get` has to replay.
+const KEYFRAME_INTERVAL: usize = 32;
+
+/// A bounded record of past generations, each held as the state from
+/// `Automaton::save`.
+///
+/// Every `KEYFRAME_INTERVAL`th state is kept whole. The ones in between
+/// only keep the bytes that changed since the generation before, XORed with
+/// their old value, with the runs of unchanged bytes skipped. Once full, the
+/// oldest generation is dropped for every new one.
+#[derive(Clone, Debug)]
+pub struct History {
+    frames: VecDeque<Frame>,
+    capacity: usize,
+    first: u64,
+    latest: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+enum Frame {
+    Key(Vec<u8>),
+    Delta(Vec<u8>),
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::new(),
+            capacity: capacity.max(1),
+            first: 0,
+            latest: vec![],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.latest.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// The oldest generation held.
+    pub fn first(&self) -> Option<u64> {
+        (!self.is_empty()).then_some(self.first)
+    }
+
+    /// The newest generation held.
+    pub fn last(&self) -> Option<u64> {
+        (!self.is_empty()).then(|| self.first + self.frames.len() as u64 - 1)
+    }
+
+    pub fn contains(&self, generation: u64) -> bool {
+        self.first().is_some_and(|first| generation >= first)
+            && self.last().is_some_and(|last| generation <= last)
+    }
+
+    /// Records `state` as `generation`. Anything already recorded from
+    /// `generation` on is dropped first, and so is everything else if
+    /// `generation` doesn't follow on from what is held.
+    pub fn push(&mut self, generation: u64, state: Vec<u8>) {
+        match self.last() {
+            Some(last) if generation == last + 1 => {}
+            Some(last) if generation > self.first && generation <= last => {
+                self.truncate(generation - 1)
+            }
+            _ => self.clear(),
+        }
+        if self.is_empty() {
+            self.first = generation;
+        }
+
+        let since_key = self
+            .frames
+            .iter()
+            .rev()
+            .take_while(|frame| matches!(frame, Frame::Delta(_)))
+            .count();
+        let frame = match self.latest.len() == state.len() && since_key + 1 < KEYFRAME_INTERVAL {
+            true => Frame::Delta(delta(&self.latest, &state)),
+            false => Frame::Key(state.clone()),
+        };
+        self.frames.push_back(frame);
+        self.latest = state;
+
+        if self.frames.len() > self.capacity {
+            // the front is always whole, so the next one can be rebuilt
+            // from it
+            if let Some(Frame::Key(mut oldest)) = self.frames.pop_front() {
+                self.first += 1;
+                if let Some(Frame::Delta(changes)) = self.frames.front() {
+                    apply(&mut oldest, changes);
+                    self.frames[0] = Frame::Key(oldest);
+                }
+            }
+        }
+    }
+
+    /// The state recorded as `generation`, if it is still held.
+    pub fn get(&self, generation: u64) -> Option<Vec<u8>> {
+        if !self.contains(generation) {
+            return None;
+        }
+
+        let index = (generation - self.first) as usize;
+        let key = (0..=index)
+            .rev()
+            .find(|&i| matches!(self.frames[i], Frame::Key(_)))?;
+
+        let mut state = vec![];
+        for frame in self.frames.range(key..=index) {
+            match frame {
+                Frame::Key(whole) => state.clone_from(whole),
+                Frame::Delta(changes) => apply(&mut state, changes),
+            }
+        }
+        Some(state)
+    }
+
+    /// Drops every generation after `generation`.
+    pub fn truncate(&mut self, generation: u64) {
+        match self.get(generation) {
+            Some(state) => {
+                self.frames.truncate((generation - self.first) as usize + 1);
+                self.latest = state;
+            }
+            None if self.first().is_some_and(|first| generation < first) => self.clear(),
+            None => {}
+        }
+    }
+}
+
+/// Runs of `(unchanged, changed)` byte counts as LEB128, each followed by
+/// the changed bytes XORed with their old value.
+fn delta(old: &[u8], new: &[u8]) -> Vec<u8> {
+    let mut out = vec![];
+    let mut i = 0;
+    while i < new.len() {
+        let start = i;
+        while i < new.len() && old[i] == new[i] {
+            i += 1;
+        }
+        let unchanged = i - start;
+
+        let start = i;
+        while i < new.len() && old[i] != new[i] {
+            i += 1;
+        }
+        if i == start {
+            break;
+        }
+
+        write_len(&mut out, unchanged);
+        write_len(&mut out, i - start);
+        out.extend(old[start..i].iter().zip(&new[start..i]).map(|(a, b)| a ^ b));
+    }
+    out
+}
+
+fn apply(state: &mut [u8], changes: &[u8]) {
+    let (mut i, mut at) = (0, 0);
+    while at < changes.len() {
+        i += read_len(changes, &mut at);
+        let changed = read_len(changes, &mut at);
+        for (byte, change) in state[i..i + changed].iter_mut().zip(&changes[at..]) {
+            *byte ^= change;
+        }
+        i += changed;
+        at += changed;
+    }
+}
+
+fn write_len(out: &mut Vec<u8>, mut len: usize) {
+    while len >= 0x80 {
+        out.push(len as u8 | 0x80);
+        len >>= 7;
+    }
+    out.push(len as u8);
+}
+
+fn read_len(bytes: &[u8], at: &mut usize) -> usize {
+    let mut len = 0;
+    let mut shift = 0;
+    loop {
+        let byte = bytes[*at];
+        *at += 1;
+        len |= ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+        if byte < 0x80 {
+            return len;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::{
+        elementary::Elementary,
+        gray_scott::GrayScott,
+        hashlife::HashLife,
+        lenia::Lenia,
+        life::Life,
+        reversible::{Margolus, SecondOrder},
+        seeder, Automaton,
+    };
+
+    type Constructor = fn() -> Box<dyn Automaton>;
+
+    /// A state per generation, each changing a few scattered bytes of the
+    /// last, long enough that the gaps between changes take more than one
+    /// byte to write.
+    fn states(count: usize) -> Vec<Vec<u8>> {
+        let mut rng = seeder::rng(count as u64);
+        let mut state: Vec<u8> = (0..1000).map(|_| rng.gen()).collect();
+        (0..count)
+            .map(|_| {
+                for _ in 0..rng.gen_range(0..8) {
+                    let i = rng.gen_range(0..state.len());
+                    state[i] = rng.gen();
+                }
+                state.clone()
+            })
+            .collect()
+    }
+
+    fn filled(capacity: usize, states: &[Vec<u8>]) -> History {
+        let mut history = History::new(capacity);
+        for (generation, state) in states.iter().enumerate() {
+            history.push(generation as u64, state.clone());
+        }
+        history
+    }
+
+    #[test]
+    fn gets_every_generation_pushed() {
+        let states = states(3 * KEYFRAME_INTERVAL + 5);
+        let history = filled(states.len(), &states);
+
+        assert_eq!(history.first(), Some(0));
+        assert_eq!(history.last(), Some(states.len() as u64 - 1));
+        for (generation, state) in states.iter().enumerate() {
+            assert_eq!(history.get(generation as u64).as_ref(), Some(state));
+        }
+        assert_eq!(history.get(states.len() as u64), None);
+    }
+
+    #[test]
+    fn evicts_the_oldest_generations() {
+        let states = states(100);
+        let history = filled(KEYFRAME_INTERVAL + 7, &states);
+
+        let first = 100 - (KEYFRAME_INTERVAL + 7) as u64;
+        assert_eq!(history.first(), Some(first));
+        assert_eq!(history.get(first - 1), None);
+        for generation in first..100 {
+            assert_eq!(
+                history.get(generation).as_ref(),
+                Some(&states[generation as usize])
+            );
+        }
+    }
+
+    #[test]
+    fn truncates_and_branches() {
+        let states = states(50);
+        let mut history = filled(64, &states);
+
+        history.truncate(40);
+        assert_eq!(history.last(), Some(40));
+        assert_eq!(history.get(41), None);
+
+        // a new 41st generation carries on from the 40th
+        let mut branch = states[40].clone();
+        branch[0] ^= 0xff;
+        history.push(41, branch.clone());
+        assert_eq!(history.get(41), Some(branch.clone()));
+        assert_eq!(history.get(40).as_ref(), Some(&states[40]));
+
+        // pushing over a held generation replaces it and everything after
+        history.push(10, branch.clone());
+        assert_eq!(history.last(), Some(10));
+        assert_eq!(history.get(10), Some(branch));
+        assert_eq!(history.get(9).as_ref(), Some(&states[9]));
+
+        history.truncate(5);
+        history.push(6, states[6].clone());
+        assert_eq!(history.get(6).as_ref(), Some(&states[6]));
+    }
+
+    #[test]
+    fn truncates_and_branches_after_eviction() {
+        let states = states(100);
+        let mut history = filled(40, &states);
+
+        history.truncate(70);
+        assert_eq!(history.first(), Some(60));
+        assert_eq!(history.last(), Some(70));
+        history.push(71, states[0].clone());
+        assert_eq!(history.get(71).as_ref(), Some(&states[0]));
+        assert_eq!(history.get(60).as_ref(), Some(&states[60]));
+
+        history.truncate(59);
+        assert!(history.is_empty());
+    }
+
+    #[test]
+    fn restores_every_engine() {
+        let engines: [(&str, Constructor); 7] = [
+            ("life", || Box::new(Life::new(24))),
+            ("lenia", || Box::new(Lenia::new(24))),
+            ("gray-scott", || Box::new(GrayScott::new(24))),
+            ("1d", || Box::new(Elementary::new(24, 24))),
+            ("hashlife", || Box::new(HashLife::new(24))),
+            ("second order", || Box::new(SecondOrder::new(24))),
+            ("margolus", || Box::new(Margolus::new(24))),
+        ];
+
+        for (name, new) in engines {
+            let mut automaton = new();
+            automaton.seed(1);
+            let mut history = History::new(64);
+            let mut cells = vec![];
+            for generation in 0..40 {
+                history.push(generation, automaton.save());
+                cells.push(automaton.cells().to_vec());
+                automaton.step();
+            }
+            cells.push(automaton.cells().to_vec());
+
+            for generation in 0..40 {
+                let mut restored = new();
+                restored.restore(&history.get(generation).unwrap());
+                assert_eq!(
+                    restored.cells(),
+                    cells[generation as usize],
+                    "{name} at generation {generation}"
+                );
+                // anything else the engine steps from comes back too
+                restored.step();
+                assert_eq!(
+                    restored.cells(),
+                    cells[generation as usize + 1],
+                    "{name} after generation {generation}"
+                );
+            }
+        }
+    }
+}
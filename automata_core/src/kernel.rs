@@ -0,0 +1,255 @@
+use crate::automaton::Automaton;
+
+/// Turns a board into the taps of an impulse response.
+///
+/// Mappers read nothing but `Automaton::cells`, so any of them works with
+/// any engine. Whatever shape the board is, every tap is filled.
+pub trait KernelMapper: Send {
+    fn map(&mut self, automaton: &dyn Automaton, taps: &mut [f32]);
+}
+
+/// Row `i`'s mean plus column `i`'s, negated on odd taps.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CrossSums;
+
+/// Each row's mean, from -1 for an empty row to 1 for a full one.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RowSums;
+
+/// Each column's mean, from -1 for an empty column to 1 for a full one.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ColumnSums;
+
+/// The mean of each diagonal running down and to the right, from the top
+/// right corner to the bottom left, scaled like `RowSums`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Diagonals;
+
+/// The cells read off along a Hilbert curve and shrunk or stretched to fit,
+/// so neighbouring taps come from neighbouring cells.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Hilbert;
+
+/// The mean of each ring of cells around the middle of the board, from the
+/// middle out, scaled like `RowSums`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Radial;
+
+/// Like `CrossSums`, but each cell counts for more the longer it has been
+/// alive, up to `AGE_LIMIT` generations. Newborn cells barely register, so
+/// the kernel follows what the board has settled on rather than what
+/// flickers.
+///
+/// Ages only move on with `update`, not with `map`, so they can be kept
+/// counting while another mapping is in use. Stepping back or switching
+/// engines carries on from the ages it has.
+#[derive(Clone, Debug, Default)]
+pub struct Age {
+    ages: Vec<u16>,
+}
+
+const AGE_LIMIT: u16 = 16;
+
+impl KernelMapper for CrossSums {
+    fn map(&mut self, automaton: &dyn Automaton, taps: &mut [f32]) {
+        cross_sums(automaton, taps, |_, cell| cell);
+    }
+}
+
+impl KernelMapper for RowSums {
+    fn map(&mut self, automaton: &dyn Automaton, taps: &mut [f32]) {
+        let (width, height) = (automaton.width(), automaton.height());
+        let rows: Vec<f32> = (0..height)
+            .map(|y| (0..width).map(|x| automaton.cell(x, y)).sum::<f32>() / width as f32)
+            .collect();
+        resample(&rows, taps);
+        bipolar(taps);
+    }
+}
+
+impl KernelMapper for ColumnSums {
+    fn map(&mut self, automaton: &dyn Automaton, taps: &mut [f32]) {
+        let (width, height) = (automaton.width(), automaton.height());
+        let columns: Vec<f32> = (0..width)
+            .map(|x| (0..height).map(|y| automaton.cell(x, y)).sum::<f32>() / height as f32)
+            .collect();
+        resample(&columns, taps);
+        bipolar(taps);
+    }
+}
+
+impl KernelMapper for Diagonals {
+    fn map(&mut self, automaton: &dyn Automaton, taps: &mut [f32]) {
+        let (width, height) = (automaton.width(), automaton.height());
+        let mut sums = vec![(0.0, 0); (width + height).saturating_sub(1)];
+        for y in 0..height {
+            for x in 0..width {
+                let (sum, count) = &mut sums[y + width - 1 - x];
+                *sum += automaton.cell(x, y);
+                *count += 1;
+            }
+        }
+
+        let means: Vec<f32> = sums
+            .iter()
+            .map(|(sum, count)| sum / *count as f32)
+            .collect();
+        resample(&means, taps);
+        bipolar(taps);
+    }
+}
+
+impl KernelMapper for Hilbert {
+    fn map(&mut self, automaton: &dyn Automaton, taps: &mut [f32]) {
+        let (width, height) = (automaton.width(), automaton.height());
+        let side = width.max(height).next_power_of_two();
+
+        // the curve fills the next power of two up, skipping what's off
+        // the board
+        let cells: Vec<f32> = (0..side * side)
+            .map(|d| hilbert_point(side, d))
+            .filter(|&(x, y)| x < width && y < height)
+            .map(|(x, y)| 2.0 * automaton.cell(x, y) - 1.0)
+            .collect();
+        resample(&cells, taps);
+    }
+}
+
+impl KernelMapper for Radial {
+    fn map(&mut self, automaton: &dyn Automaton, taps: &mut [f32]) {
+        let (width, height) = (automaton.width(), automaton.height());
+        let (cx, cy) = ((width as f32 - 1.0) / 2.0, (height as f32 - 1.0) / 2.0);
+        let rings = cx.hypot(cy).round() as usize + 1;
+
+        let mut sums = vec![(0.0, 0); rings];
+        for y in 0..height {
+            for x in 0..width {
+                let ring = (x as f32 - cx).hypot(y as f32 - cy).round() as usize;
+                let (sum, count) = &mut sums[ring.min(rings - 1)];
+                *sum += automaton.cell(x, y);
+                *count += 1;
+            }
+        }
+
+        // rings too thin to hold a cell take the one inside them
+        let mut means = Vec::with_capacity(rings);
+        for (sum, count) in sums {
+            let mean = match count {
+                0 => means.last().copied().unwrap_or(0.0),
+                count => sum / count as f32,
+            };
+            means.push(mean);
+        }
+        resample(&means, taps);
+        bipolar(taps);
+    }
+}
+
+impl Age {
+    /// Counts one more generation for every live cell, and restarts the
+    /// rest.
+    pub fn update(&mut self, automaton: &dyn Automaton) {
+        let cells = automaton.cells();
+        if self.ages.len() != cells.len() {
+            self.ages = vec![0; cells.len()];
+        }
+        for (age, cell) in self.ages.iter_mut().zip(cells) {
+            *age = match *cell >= 0.5 {
+                true => (*age + 1).min(AGE_LIMIT),
+                false => 0,
+            };
+        }
+    }
+}
+
+impl KernelMapper for Age {
+    fn map(&mut self, automaton: &dyn Automaton, taps: &mut [f32]) {
+        // ages counted over a board of another size don't line up
+        let ages = match self.ages.len() == automaton.cells().len() {
+            true => &self.ages[..],
+            false => &[],
+        };
+        cross_sums(automaton, taps, |i, cell| {
+            cell * ages.get(i).copied().unwrap_or(0) as f32 / AGE_LIMIT as f32
+        });
+    }
+}
+
+/// The `CrossSums` mapping, with each cell's value passed through `weight`
+/// along with its index.
+fn cross_sums(automaton: &dyn Automaton, taps: &mut [f32], weight: impl Fn(usize, f32) -> f32) {
+    let (width, height) = (automaton.width(), automaton.height());
+    let mut rows = vec![0.0; height];
+    let mut columns = vec![0.0; width];
+    for (i, cell) in automaton.cells().iter().enumerate() {
+        let value = weight(i, *cell);
+        rows[i / width] += value / width as f32;
+        columns[i % width] += value / height as f32;
+    }
+
+    let mut column_taps = vec![0.0; taps.len()];
+    resample(&rows, taps);
+    resample(&columns, &mut column_taps);
+    for (i, (tap, column)) in taps.iter_mut().zip(column_taps).enumerate() {
+        *tap = match i % 2 == 0 {
+            true => *tap + column,
+            false => -(*tap + column),
+        };
+    }
+}
+
+/// Fits `values` to `taps`, averaging them down when there are more values
+/// than taps and interpolating between them when there are fewer.
+fn resample(values: &[f32], taps: &mut [f32]) {
+    let (len, n) = (values.len(), taps.len());
+    if len == 0 {
+        taps.fill(0.0);
+        return;
+    }
+
+    if len >= n {
+        for (i, tap) in taps.iter_mut().enumerate() {
+            let bin = &values[i * len / n..(i + 1) * len / n];
+            *tap = bin.iter().sum::<f32>() / bin.len() as f32;
+        }
+    } else {
+        let scale = (len - 1) as f32 / (n - 1).max(1) as f32;
+        for (i, tap) in taps.iter_mut().enumerate() {
+            let position = i as f32 * scale;
+            let (before, t) = (position as usize, position.fract());
+            let after = (before + 1).min(len - 1);
+            *tap = values[before] + (values[after] - values[before]) * t;
+        }
+    }
+}
+
+/// Maps densities in `[0, 1]` to `[-1, 1]`.
+fn bipolar(taps: &mut [f32]) {
+    for tap in taps {
+        *tap = 2.0 * *tap - 1.0;
+    }
+}
+
+/// The `d`th point along a Hilbert curve filling a `side` by `side` square,
+/// `side` being a power of two.
+fn hilbert_point(side: usize, d: usize) -> (usize, usize) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut s = 1;
+    while s < side {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
@@ -0,0 +1,191 @@
+use rand::Rng;
+
+use crate::{automaton::Automaton, seeder, topology::Topology};
+
+/// A continuous Life-like automaton in the style of Lenia.
+///
+/// Every cell holds a value in `[0, 1]`. Each step the board is convolved
+/// with a smooth ring shaped kernel of the given radius, the result is fed
+/// through a gaussian growth function centered on `mu` with width `sigma`,
+/// and a `dt` sized slice of that growth is added back onto the cells.
+pub struct Lenia {
+    size: usize,
+    topology: Topology,
+    radius: f32,
+    mu: f32,
+    sigma: f32,
+    dt: f32,
+    kernel: Vec<(i32, i32, f32)>,
+    cells: Vec<f32>,
+    next: Vec<f32>,
+}
+
+impl Lenia {
+    pub fn new(size: usize) -> Self {
+        let mut lenia = Self {
+            size,
+            topology: Topology::default(),
+            radius: 0.0,
+            mu: 0.15,
+            sigma: 0.015,
+            dt: 0.1,
+            kernel: vec![],
+            cells: vec![0.0; size * size],
+            next: vec![0.0; size * size],
+        };
+        lenia.set_radius(5.0);
+        lenia
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> f32 {
+        self.cells[y * self.size + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: f32) {
+        self.cells[y * self.size + x] = value.clamp(0.0, 1.0);
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(0.0);
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    /// Sets the kernel radius in cells, rebuilding the kernel if it changed.
+    pub fn set_radius(&mut self, radius: f32) {
+        if radius == self.radius {
+            return;
+        }
+        self.radius = radius;
+
+        // a single ring with a smooth bump profile, normalized to sum to 1
+        let r = radius.ceil() as i32;
+        self.kernel.clear();
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let dist = ((dx * dx + dy * dy) as f32).sqrt() / radius;
+                if dist > 0.0 && dist < 1.0 {
+                    let weight = (4.0 - 1.0 / (dist * (1.0 - dist))).exp();
+                    self.kernel.push((dx, dy, weight));
+                }
+            }
+        }
+
+        let total: f32 = self.kernel.iter().map(|(_, _, w)| w).sum();
+        for (_, _, weight) in &mut self.kernel {
+            *weight /= total;
+        }
+    }
+
+    pub fn set_growth(&mut self, mu: f32, sigma: f32) {
+        self.mu = mu;
+        self.sigma = sigma;
+    }
+
+    pub fn set_dt(&mut self, dt: f32) {
+        self.dt = dt;
+    }
+
+    /// Fills a square patch in the middle of the board with uniform noise,
+    /// leaving the rest empty so the noise has room to organize.
+    pub fn randomize<R: Rng>(&mut self, rng: &mut R) {
+        self.clear();
+
+        let start = self.size / 4;
+        let end = self.size - start;
+        for y in start..end {
+            for x in start..end {
+                self.cells[y * self.size + x] = rng.gen();
+            }
+        }
+    }
+
+    fn growth(&self, potential: f32) -> f32 {
+        let d = (potential - self.mu) / self.sigma;
+        2.0 * (-0.5 * d * d).exp() - 1.0
+    }
+}
+
+impl Automaton for Lenia {
+    fn step(&mut self) {
+        let n = self.size;
+        for y in 0..n {
+            for x in 0..n {
+                let mut potential = 0.0;
+                for (dx, dy, weight) in &self.kernel {
+                    if let Some((nx, ny)) = self.topology.map(x as i32 + dx, y as i32 + dy, n) {
+                        potential += weight * self.cells[ny * n + nx];
+                    }
+                }
+
+                let growth = self.growth(potential);
+                self.next[y * n + x] = (self.cells[y * n + x] + self.dt * growth).clamp(0.0, 1.0);
+            }
+        }
+
+        std::mem::swap(&mut self.cells, &mut self.next);
+    }
+
+    fn width(&self) -> usize {
+        self.size
+    }
+
+    fn height(&self) -> usize {
+        self.size
+    }
+
+    fn cells(&self) -> &[f32] {
+        &self.cells
+    }
+
+    fn reset(&mut self) {
+        self.clear();
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.randomize(&mut seeder::rng(seed));
+    }
+
+    fn load(&mut self, cells: &[(i64, i64)]) {
+        self.clear();
+        let size = self.size as i64;
+        for &(x, y) in cells {
+            if (0..size).contains(&x) && (0..size).contains(&y) {
+                self.cells[(y * size + x) as usize] = 1.0;
+            }
+        }
+    }
+
+    fn flip(&mut self, cells: &[(i64, i64)]) {
+        let size = self.size as i64;
+        for &(x, y) in cells {
+            if (0..size).contains(&x) && (0..size).contains(&y) {
+                let cell = &mut self.cells[(y * size + x) as usize];
+                *cell = 1.0 - *cell;
+            }
+        }
+    }
+
+    fn save(&self) -> Vec<u8> {
+        self.cells
+            .iter()
+            .flat_map(|cell| cell.to_le_bytes())
+            .collect()
+    }
+
+    fn restore(&mut self, state: &[u8]) {
+        if state.len() != self.cells.len() * 4 {
+            return;
+        }
+
+        for (cell, bytes) in self.cells.iter_mut().zip(state.chunks_exact(4)) {
+            *cell = f32::from_le_bytes(bytes.try_into().unwrap());
+        }
+    }
+
+    fn population(&self) -> usize {
+        self.cells.iter().filter(|cell| **cell >= 0.5).count()
+    }
+}
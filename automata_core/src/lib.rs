@@ -0,0 +1,25 @@
+pub mod automaton;
+pub mod board;
+pub mod cycle;
+pub mod elementary;
+pub mod export;
+pub mod gray_scott;
+pub mod hashlife;
+pub mod history;
+pub mod kernel;
+pub mod lenia;
+pub mod library;
+pub mod life;
+pub mod neighborhood;
+pub mod normalize;
+pub mod pattern;
+pub mod pool;
+pub mod post;
+pub mod reversible;
+pub mod rule;
+pub mod seeder;
+pub mod spectrum;
+pub mod stats;
+pub mod topology;
+
+pub use automaton::Automaton;
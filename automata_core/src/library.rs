@@ -0,0 +1,205 @@
+use std::fmt;
+
+use crate::pattern::Pattern;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Category {
+    StillLife,
+    Oscillator,
+    Spaceship,
+    Gun,
+    Puffer,
+    /// Small patterns that take a long time to settle.
+    Methuselah,
+}
+
+/// How far a moving pattern travels in one period, in cells, with x to the
+/// east and y to the south.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Velocity {
+    pub dx: i32,
+    pub dy: i32,
+    pub period: u32,
+}
+
+/// A compiled-in pattern with what is known about it under B3/S23.
+#[derive(Clone, Copy, Debug)]
+pub struct Entry {
+    pub name: &'static str,
+    pub category: Category,
+    /// `None` for patterns that never repeat.
+    pub period: Option<u32>,
+    pub velocity: Option<Velocity>,
+    rle: &'static str,
+}
+
+pub const LIBRARY: &[Entry] = &[
+    still("block", "x = 2, y = 2, rule = B3/S23\n2o$2o!"),
+    still("beehive", "x = 4, y = 3, rule = B3/S23\nb2o$o2bo$b2o!"),
+    still("loaf", "x = 4, y = 4, rule = B3/S23\nb2o$o2bo$bobo$2bo!"),
+    still("boat", "x = 3, y = 3, rule = B3/S23\n2o$obo$bo!"),
+    still("tub", "x = 3, y = 3, rule = B3/S23\nbo$obo$bo!"),
+    oscillator("blinker", 2, "x = 3, y = 1, rule = B3/S23\n3o!"),
+    oscillator("toad", 2, "x = 4, y = 2, rule = B3/S23\nb3o$3o!"),
+    oscillator("beacon", 2, "x = 4, y = 4, rule = B3/S23\n2o$2o$2b2o$2b2o!"),
+    oscillator(
+        "pulsar",
+        3,
+        "x = 13, y = 13, rule = B3/S23\n2b3o3b3o2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2$\
+         2b3o3b3o$o4bobo4bo$o4bobo4bo$o4bobo4bo2$2b3o3b3o!",
+    ),
+    oscillator(
+        "pentadecathlon",
+        15,
+        "x = 10, y = 3, rule = B3/S23\n2bo4bo$2ob4ob2o$2bo4bo!",
+    ),
+    moving(
+        "glider",
+        Category::Spaceship,
+        (1, 1, 4),
+        "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!",
+    ),
+    moving(
+        "lightweight spaceship",
+        Category::Spaceship,
+        (-2, 0, 4),
+        "x = 5, y = 4, rule = B3/S23\nbo2bo$o$o3bo$4o!",
+    ),
+    moving(
+        "middleweight spaceship",
+        Category::Spaceship,
+        (-2, 0, 4),
+        "x = 6, y = 5, rule = B3/S23\n3bo$bo3bo$o$o4bo$5o!",
+    ),
+    moving(
+        "heavyweight spaceship",
+        Category::Spaceship,
+        (-2, 0, 4),
+        "x = 7, y = 5, rule = B3/S23\n3b2o$bo4bo$o$o5bo$6o!",
+    ),
+    Entry {
+        name: "gosper glider gun",
+        category: Category::Gun,
+        period: Some(30),
+        velocity: None,
+        rle: "x = 36, y = 9, rule = B3/S23\n24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$\
+              2o8bo5bo3b2o$2o8bo3bob2o4bobo$10bo5bo7bo$11bo3bo$12b2o!",
+    },
+    moving(
+        "puffer train",
+        Category::Puffer,
+        (70, 0, 140),
+        "x = 5, y = 18, rule = B3/S23\n3bo$4bo$o3bo$b4o4$o$b2o$2bo$2bo$bo3$3bo$4bo$o3bo$b4o!",
+    ),
+    // settles after 1103 generations
+    methuselah("r-pentomino", "x = 3, y = 3, rule = B3/S23\nb2o$2o$bo!"),
+    // settles after 5206 generations
+    methuselah("acorn", "x = 7, y = 3, rule = B3/S23\nbo$3bo$2o2b3o!"),
+    // dies out after 130 generations
+    methuselah("diehard", "x = 8, y = 3, rule = B3/S23\n6bo$2o$bo3b3o!"),
+    // the piston_gol starting shape, settles after 1147 generations
+    methuselah(
+        "explode",
+        "x = 3, y = 6, rule = B3/S23\n2bo$obo$b2o$b2o$obo$2bo!",
+    ),
+];
+
+const fn still(name: &'static str, rle: &'static str) -> Entry {
+    Entry {
+        name,
+        category: Category::StillLife,
+        period: Some(1),
+        velocity: None,
+        rle,
+    }
+}
+
+const fn oscillator(name: &'static str, period: u32, rle: &'static str) -> Entry {
+    Entry {
+        name,
+        category: Category::Oscillator,
+        period: Some(period),
+        velocity: None,
+        rle,
+    }
+}
+
+const fn moving(
+    name: &'static str,
+    category: Category,
+    (dx, dy, period): (i32, i32, u32),
+    rle: &'static str,
+) -> Entry {
+    Entry {
+        name,
+        category,
+        period: Some(period),
+        velocity: Some(Velocity { dx, dy, period }),
+        rle,
+    }
+}
+
+const fn methuselah(name: &'static str, rle: &'static str) -> Entry {
+    Entry {
+        name,
+        category: Category::Methuselah,
+        period: None,
+        velocity: None,
+        rle,
+    }
+}
+
+impl Entry {
+    pub fn pattern(&self) -> Pattern {
+        let mut pattern = Pattern::parse_rle(self.rle).expect("library patterns are valid RLE");
+        pattern.name = Some(self.name.to_string());
+        pattern
+    }
+}
+
+/// Looks an entry up by name, ignoring case.
+pub fn find(name: &str) -> Option<&'static Entry> {
+    LIBRARY
+        .iter()
+        .find(|entry| entry.name.eq_ignore_ascii_case(name))
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Category::StillLife => "still life",
+            Category::Oscillator => "oscillator",
+            Category::Spaceship => "spaceship",
+            Category::Gun => "gun",
+            Category::Puffer => "puffer",
+            Category::Methuselah => "methuselah",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Speeds as Life enthusiasts write them, e.g. `c/4 diagonal`.
+impl fmt::Display for Velocity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let distance = self.dx.unsigned_abs().max(self.dy.unsigned_abs());
+        let divisor = gcd(distance, self.period);
+        let (distance, period) = (distance / divisor, self.period / divisor);
+
+        match distance {
+            1 => write!(f, "c/{period}")?,
+            d => write!(f, "{d}c/{period}")?,
+        }
+        match (self.dx, self.dy) {
+            (0, _) | (_, 0) => write!(f, " orthogonal"),
+            (dx, dy) if dx.abs() == dy.abs() => write!(f, " diagonal"),
+            _ => write!(f, " oblique"),
+        }
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    match b {
+        0 => a.max(1),
+        b => gcd(b, a % b),
+    }
+}
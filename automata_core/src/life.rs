@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::{
+    automaton::Automaton,
+    board::{BitBoard, Board},
+    neighborhood::Neighborhood,
+    pattern::Pattern,
+    pool::Pool,
+    rule::Rule,
+    seeder,
+    topology::Topology,
+};
+
+/// Life-like and Generations rules running on a `BitBoard`.
+pub struct Life {
+    board: BitBoard,
+    rule: Rule,
+    field: Vec<f32>,
+}
+
+impl Life {
+    pub fn new(size: usize) -> Self {
+        Self {
+            board: BitBoard::new(size),
+            rule: Rule::default(),
+            field: vec![0.0; size * size],
+        }
+    }
+
+    pub fn board(&self) -> &BitBoard {
+        &self.board
+    }
+
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        if rule != self.rule {
+            self.rule = rule;
+            self.refresh_field();
+        }
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.board.set_topology(topology);
+    }
+
+    pub fn set_neighborhood(&mut self, neighborhood: Neighborhood) {
+        if *self.board.neighborhood() != neighborhood {
+            self.board.set_neighborhood(neighborhood);
+        }
+    }
+
+    /// Shares out the steps of large boards, see `BitBoard`.
+    pub fn set_pool(&mut self, pool: Option<Arc<Pool>>) {
+        self.board.set_pool(pool);
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, alive: bool) {
+        self.board.set(x, y, alive);
+        self.field[y * self.board.size() + x] = self.rule.weight(self.board.state(x, y));
+    }
+
+    /// Brings the given cells to life, on top of whatever is already on the
+    /// board, dropping any that fall outside it.
+    pub fn stamp(&mut self, cells: impl IntoIterator<Item = (i64, i64)>) {
+        let size = self.board.size() as i64;
+        for (x, y) in cells {
+            if (0..size).contains(&x) && (0..size).contains(&y) {
+                self.board.set(x as usize, y as usize, true);
+            }
+        }
+
+        self.refresh_field();
+    }
+
+    /// The live cells and the rule, cropped to their bounding box.
+    pub fn pattern(&self) -> Pattern {
+        let size = self.board.size();
+        let mut cells = vec![];
+        for y in 0..size {
+            for x in 0..size {
+                if self.board.state(x, y) == 1 {
+                    cells.push((x as i64, y as i64));
+                }
+            }
+        }
+        Pattern::from_cells(None, Some(self.rule), &cells)
+    }
+
+    pub fn randomize<R: Rng>(&mut self, rng: &mut R) {
+        self.board.clear();
+
+        for i in 0..self.board.size() {
+            for j in 0..self.board.size() {
+                if rng.gen() {
+                    self.board.set(i, j, true);
+                }
+            }
+        }
+
+        self.refresh_field();
+    }
+
+    fn refresh_field(&mut self) {
+        let size = self.board.size();
+        for y in 0..size {
+            for x in 0..size {
+                self.field[y * size + x] = self.rule.weight(self.board.state(x, y));
+            }
+        }
+    }
+}
+
+impl Automaton for Life {
+    fn step(&mut self) {
+        self.board.step(&self.rule);
+        self.refresh_field();
+    }
+
+    fn width(&self) -> usize {
+        self.board.size()
+    }
+
+    fn height(&self) -> usize {
+        self.board.size()
+    }
+
+    fn cells(&self) -> &[f32] {
+        &self.field
+    }
+
+    fn reset(&mut self) {
+        self.board.clear();
+        self.field.fill(0.0);
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.randomize(&mut seeder::rng(seed));
+    }
+
+    fn load(&mut self, cells: &[(i64, i64)]) {
+        self.reset();
+        self.stamp(cells.iter().copied());
+    }
+
+    /// Refractory cells count as dead and come back to life.
+    fn flip(&mut self, cells: &[(i64, i64)]) {
+        let size = self.board.size() as i64;
+        for &(x, y) in cells {
+            if (0..size).contains(&x) && (0..size).contains(&y) {
+                let (x, y) = (x as usize, y as usize);
+                self.set(x, y, self.board.state(x, y) != 1);
+            }
+        }
+    }
+
+    /// One byte per cell, holding its `Board::state`.
+    fn save(&self) -> Vec<u8> {
+        let size = self.board.size();
+        (0..size * size)
+            .map(|i| self.board.state(i % size, i / size))
+            .collect()
+    }
+
+    fn restore(&mut self, state: &[u8]) {
+        let size = self.board.size();
+        if state.len() != size * size {
+            return;
+        }
+
+        for (i, cell) in state.iter().enumerate() {
+            self.board.set_state(i % size, i / size, *cell);
+        }
+        self.refresh_field();
+    }
+
+    fn population(&self) -> usize {
+        self.board.population()
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt;
+
+use crate::rule::MAX_COUNT;
+
+/// The set of cells whose states are summed to get a cell's neighbor count.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Neighborhood {
+    /// Every cell within Chebyshev distance `r`. `Moore(1)` is the classic
+    /// eight cell neighborhood and takes the word parallel fast path.
+    Moore(u8),
+    /// Every cell within Manhattan distance `r`, i.e. a diamond.
+    VonNeumann(u8),
+    /// The six neighbors of a hexagonal grid stored in "odd-r" offset
+    /// coordinates, where odd rows are shunted half a cell to the right.
+    Hexagonal,
+    /// A square, odd sized grid of weights centered on the cell.
+    Custom(Mask),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mask {
+    radius: u8,
+    weights: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MaskError {
+    Empty,
+    NotSquare,
+    EvenSize,
+    InvalidWeight(char),
+    /// The neighbors' weights add up to more than `MAX_COUNT`, so some
+    /// counts could never be told apart by a rule.
+    TooManyNeighbors(u32),
+}
+
+impl fmt::Display for MaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaskError::Empty => write!(f, "empty mask"),
+            MaskError::NotSquare => write!(f, "every row of the mask must be as long as there are rows"),
+            MaskError::EvenSize => write!(f, "the mask must have an odd size so it has a center"),
+            MaskError::InvalidWeight(c) => write!(f, "invalid weight '{c}'"),
+            MaskError::TooManyNeighbors(n) => write!(
+                f,
+                "neighbor counts reach {n}, but rules only count up to {MAX_COUNT}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MaskError {}
+
+impl Default for Neighborhood {
+    fn default() -> Self {
+        Neighborhood::Moore(1)
+    }
+}
+
+impl Neighborhood {
+    /// The neighbor offsets `(dx, dy, weight)` for a cell in row `y`. Only
+    /// the hexagonal neighborhood depends on the row.
+    pub fn offsets(&self, y: usize) -> Vec<(i32, i32, u8)> {
+        let odd = y % 2 == 1;
+
+        match self {
+            Neighborhood::Moore(r) => square(*r as i32)
+                .filter(|(dx, dy)| *dx != 0 || *dy != 0)
+                .map(|(dx, dy)| (dx, dy, 1))
+                .collect(),
+            Neighborhood::VonNeumann(r) => square(*r as i32)
+                .filter(|(dx, dy)| (*dx != 0 || *dy != 0) && dx.abs() + dy.abs() <= *r as i32)
+                .map(|(dx, dy)| (dx, dy, 1))
+                .collect(),
+            Neighborhood::Hexagonal => {
+                let shift = odd as i32;
+                vec![
+                    (-1, 0, 1),
+                    (1, 0, 1),
+                    (shift - 1, -1, 1),
+                    (shift, -1, 1),
+                    (shift - 1, 1, 1),
+                    (shift, 1, 1),
+                ]
+            }
+            Neighborhood::Custom(mask) => {
+                let r = mask.radius as i32;
+                let side = 2 * r + 1;
+                square(r)
+                    .filter(|(dx, dy)| *dx != 0 || *dy != 0)
+                    .map(|(dx, dy)| (dx, dy, mask.weights[((dy + r) * side + dx + r) as usize]))
+                    .filter(|(_, _, w)| *w != 0)
+                    .collect()
+            }
+        }
+    }
+
+    /// The largest possible neighbor count.
+    pub fn max_count(&self) -> u32 {
+        self.offsets(0).iter().map(|(_, _, w)| *w as u32).sum()
+    }
+
+    /// Fails if neighbor counts can go past what a rule can count, as they
+    /// do for `Moore` from radius 4.
+    pub fn check(&self) -> Result<(), MaskError> {
+        match self.max_count() {
+            n if n > MAX_COUNT as u32 => Err(MaskError::TooManyNeighbors(n)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Mask {
+    /// Parses rows of single digit weights separated by `/` or newlines, e.g.
+    /// `010/101/010` for the von Neumann neighborhood. The center weight is
+    /// ignored.
+    pub fn parse(s: &str) -> Result<Self, MaskError> {
+        let rows: Vec<&str> = s
+            .split(['/', '\n'])
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+            .collect();
+
+        if rows.is_empty() {
+            return Err(MaskError::Empty);
+        }
+        if rows.iter().any(|row| row.chars().count() != rows.len()) {
+            return Err(MaskError::NotSquare);
+        }
+        if rows.len().is_multiple_of(2) {
+            return Err(MaskError::EvenSize);
+        }
+
+        let mut weights = Vec::with_capacity(rows.len() * rows.len());
+        for c in rows.iter().flat_map(|row| row.chars()) {
+            match c.to_digit(10) {
+                Some(w) => weights.push(w as u8),
+                None => return Err(MaskError::InvalidWeight(c)),
+            }
+        }
+
+        let mask = Self {
+            radius: (rows.len() / 2) as u8,
+            weights,
+        };
+        Neighborhood::Custom(mask.clone()).check()?;
+        Ok(mask)
+    }
+}
+
+impl fmt::Display for Mask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = 2 * self.radius as usize + 1;
+        for (i, row) in self.weights.chunks(side).enumerate() {
+            if i > 0 {
+                write!(f, "/")?;
+            }
+            for w in row {
+                write!(f, "{w}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn square(r: i32) -> impl Iterator<Item = (i32, i32)> {
+    (-r..=r).flat_map(move |dy| (-r..=r).map(move |dx| (dx, dy)))
+}
@@ -0,0 +1,170 @@
+use std::{f32::consts::PI, fmt};
+
+/// The most a kernel is scaled up, about 24 dB. Anything needing more is
+/// all but silent, and would blast once it stops being silent.
+pub const MAX_GAIN: f32 = 16.0;
+
+/// The smallest the taps' sum can be as a share of their summed sizes for
+/// `DcGain`. Below it the positive and negative taps all but cancel, and
+/// bringing DC up to unity would boost everything else far past it.
+pub const MIN_BALANCE: f32 = 1e-3;
+
+/// How many frequencies from DC to Nyquist `MaxMagnitude` checks.
+const RESPONSE_POINTS: usize = 256;
+
+/// How a kernel's taps are scaled to a sensible level.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Normalization {
+    /// The taps sum to 1 or -1, so DC passes at unity. The sign is kept, so
+    /// the kernel doesn't flip over as the sum crosses zero, and kernels
+    /// whose positive and negative taps cancel out can't be normalized this
+    /// way.
+    #[default]
+    DcGain,
+    /// The largest tap is as large as those of a moving average over the
+    /// same number of taps.
+    Peak,
+    /// The taps' RMS matches that of a moving average over the same number
+    /// of taps.
+    Rms,
+    /// The squared taps sum to 1, so white noise keeps its level.
+    Energy,
+    /// The loudest frequency passes at unity, so nothing is boosted.
+    MaxMagnitude,
+}
+
+/// Why a kernel couldn't be normalized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Degenerate {
+    /// A tap is NaN or infinite.
+    NotFinite,
+    /// The quantity being normalized is zero, or so close to it that the
+    /// kernel would need more than `MAX_GAIN`.
+    Silent(f32),
+    /// The taps cancel out, summing to this share of their sizes, which is
+    /// under `MIN_BALANCE`.
+    Cancelling(f32),
+}
+
+impl fmt::Display for Degenerate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Degenerate::NotFinite => write!(f, "kernel has a NaN or infinite tap"),
+            Degenerate::Silent(level) => {
+                write!(f, "kernel level {level} is too close to zero to normalize")
+            }
+            Degenerate::Cancelling(balance) => {
+                write!(
+                    f,
+                    "kernel taps cancel out, summing to {balance} of their size"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Degenerate {}
+
+impl Normalization {
+    /// Scales `taps` in place, leaving them alone if they are degenerate.
+    pub fn apply(&self, taps: &mut [f32]) -> Result<(), Degenerate> {
+        if taps.iter().any(|tap| !tap.is_finite()) {
+            return Err(Degenerate::NotFinite);
+        }
+
+        let n = taps.len().max(1) as f32;
+        let (level, target) = match self {
+            Normalization::DcGain => {
+                let sum = taps.iter().sum::<f32>().abs();
+                let size = taps.iter().map(|tap| tap.abs()).sum::<f32>();
+                if size > 0.0 && sum / size < MIN_BALANCE {
+                    return Err(Degenerate::Cancelling(sum / size));
+                }
+                (sum, 1.0)
+            }
+            Normalization::Peak => (
+                taps.iter().fold(0.0, |peak, tap| tap.abs().max(peak)),
+                1.0 / n,
+            ),
+            Normalization::Rms => {
+                let rms = (taps.iter().map(|tap| tap * tap).sum::<f32>() / n).sqrt();
+                (rms, 1.0 / n)
+            }
+            Normalization::Energy => (taps.iter().map(|tap| tap * tap).sum::<f32>().sqrt(), 1.0),
+            Normalization::MaxMagnitude => (max_magnitude(taps), 1.0),
+        };
+
+        let gain = target / level;
+        if !gain.is_finite() || gain.abs() > MAX_GAIN {
+            return Err(Degenerate::Silent(level));
+        }
+        for tap in taps {
+            *tap *= gain;
+        }
+        Ok(())
+    }
+}
+
+/// The largest magnitude of the taps' frequency response.
+fn max_magnitude(taps: &[f32]) -> f32 {
+    (0..=RESPONSE_POINTS)
+        .map(|k| {
+            let w = PI * k as f32 / RESPONSE_POINTS as f32;
+            let (re, im) = taps
+                .iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(re, im), (i, tap)| {
+                    let (sin, cos) = (w * i as f32).sin_cos();
+                    (re + tap * cos, im - tap * sin)
+                });
+            f32::hypot(re, im)
+        })
+        .fold(0.0, f32::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_cancelling_taps() {
+        // the gain needed is only 5, but the sum is 5e-4 of the taps' sizes
+        let mut taps = [100.0, -100.0, 100.0, -99.8];
+        assert!(matches!(
+            Normalization::DcGain.apply(&mut taps),
+            Err(Degenerate::Cancelling(_))
+        ));
+        assert_eq!(taps, [100.0, -100.0, 100.0, -99.8]);
+
+        let mut taps = [0.0; 4];
+        assert!(matches!(
+            Normalization::DcGain.apply(&mut taps),
+            Err(Degenerate::Silent(_))
+        ));
+    }
+
+    #[test]
+    fn normalizes_balanced_taps() {
+        let mut taps = [1.0, -1.0, 1.0, -0.5];
+        Normalization::DcGain.apply(&mut taps).unwrap();
+        assert!((taps.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+
+        let mut taps = [0.5, 0.25, -0.25];
+        Normalization::Energy.apply(&mut taps).unwrap();
+        assert!((taps.iter().map(|tap| tap * tap).sum::<f32>() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn refuses_gains_past_the_limit() {
+        let mut taps = [0.01, 0.0, 0.0];
+        assert!(matches!(
+            Normalization::DcGain.apply(&mut taps),
+            Err(Degenerate::Silent(_))
+        ));
+        let mut taps = [1.0, f32::NAN];
+        assert_eq!(
+            Normalization::Peak.apply(&mut taps),
+            Err(Degenerate::NotFinite)
+        );
+    }
+}
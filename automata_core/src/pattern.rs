@@ -0,0 +1,575 @@
+use std::fmt;
+
+use crate::rule::{Rule, RuleError};
+
+/// A finite two state pattern, as loaded from the usual pattern file formats.
+///
+/// Live cells are stored relative to the top left corner of the pattern's
+/// bounding box. Patterns for Generations rules keep only their live cells,
+/// dropping the refractory ones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    pub name: Option<String>,
+    pub width: usize,
+    pub height: usize,
+    /// The rule from an RLE header, if there was one.
+    pub rule: Option<Rule>,
+    cells: Vec<(usize, usize)>,
+}
+
+/// Quarter turns clockwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    None,
+    Quarter,
+    Half,
+    ThreeQuarters,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatternError {
+    Empty,
+    InvalidHeader {
+        line: usize,
+        header: String,
+    },
+    InvalidRule {
+        line: usize,
+        error: RuleError,
+    },
+    UnexpectedChar {
+        line: usize,
+        column: usize,
+        c: char,
+    },
+    InvalidCoordinates {
+        line: usize,
+        text: String,
+    },
+    OutsideBounds {
+        line: usize,
+        width: usize,
+        height: usize,
+    },
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::Empty => write!(f, "no pattern found"),
+            PatternError::InvalidHeader { line, header } => {
+                write!(f, "line {line}: invalid RLE header '{header}'")
+            }
+            PatternError::InvalidRule { line, error } => write!(f, "line {line}: {error}"),
+            PatternError::UnexpectedChar { line, column, c } => {
+                write!(f, "line {line}, column {column}: unexpected '{c}'")
+            }
+            PatternError::InvalidCoordinates { line, text } => {
+                write!(f, "line {line}: expected two integers, found '{text}'")
+            }
+            PatternError::OutsideBounds {
+                line,
+                width,
+                height,
+            } => write!(
+                f,
+                "line {line}: cells run outside the declared {width}x{height} bounds"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+impl Pattern {
+    /// Builds a pattern from live cells at any coordinates, moving them so
+    /// the bounding box starts at the origin.
+    pub fn from_cells(name: Option<String>, rule: Option<Rule>, cells: &[(i64, i64)]) -> Self {
+        let min_x = cells.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let min_y = cells.iter().map(|&(_, y)| y).min().unwrap_or(0);
+
+        let mut cells: Vec<(usize, usize)> = cells
+            .iter()
+            .map(|&(x, y)| ((x - min_x) as usize, (y - min_y) as usize))
+            .collect();
+        cells.sort_unstable_by_key(|&(x, y)| (y, x));
+        cells.dedup();
+
+        Self {
+            name,
+            width: cells.iter().map(|&(x, _)| x + 1).max().unwrap_or(0),
+            height: cells.iter().map(|&(_, y)| y + 1).max().unwrap_or(0),
+            rule,
+            cells,
+        }
+    }
+
+    /// Parses any of the supported formats, telling them apart by their
+    /// first lines.
+    pub fn parse(text: &str) -> Result<Self, PatternError> {
+        let first = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .ok_or(PatternError::Empty)?;
+
+        if first.starts_with("#Life 1.06") {
+            return Self::parse_life_106(text);
+        }
+
+        let is_rle = text
+            .lines()
+            .map(str::trim_start)
+            .find(|line| !line.starts_with('#') && !line.is_empty())
+            .is_some_and(|line| line.starts_with('x'));
+        match is_rle {
+            true => Self::parse_rle(text),
+            false => Self::parse_plaintext(text),
+        }
+    }
+
+    /// Parses run length encoded patterns, e.g.
+    ///
+    /// ```text
+    /// #N Glider
+    /// x = 3, y = 3, rule = B3/S23
+    /// bob$2bo$3o!
+    /// ```
+    ///
+    /// Generations patterns use `.` for dead cells and `A` upwards for the
+    /// live and refractory states.
+    pub fn parse_rle(text: &str) -> Result<Self, PatternError> {
+        let mut name = None;
+        let mut header = None;
+        let mut cells = vec![];
+        let (mut x, mut y) = (0, 0);
+        let mut run = 0;
+
+        'lines: for (i, line) in text.lines().enumerate() {
+            let number = i + 1;
+            let line = line.trim();
+
+            if let Some(comment) = line.strip_prefix('#') {
+                if let Some(n) = comment.strip_prefix('N') {
+                    name = Some(n.trim().to_string());
+                }
+                continue;
+            }
+            if line.is_empty() {
+                continue;
+            }
+
+            let (width, height, _) = match header {
+                Some(header) => header,
+                None => {
+                    header = Some(parse_rle_header(line, number)?);
+                    continue;
+                }
+            };
+
+            for (column, c) in line.chars().enumerate() {
+                match c {
+                    '0'..='9' => run = run * 10 + c as usize - '0' as usize,
+                    'b' | '.' | 'o' | 'A'..='X' => {
+                        let count = run.max(1);
+                        if x + count > width || y >= height {
+                            return Err(PatternError::OutsideBounds {
+                                line: number,
+                                width,
+                                height,
+                            });
+                        }
+                        if matches!(c, 'o' | 'A') {
+                            cells.extend((x..x + count).map(|x| (x, y)));
+                        }
+                        x += count;
+                        run = 0;
+                    }
+                    '$' => {
+                        y += run.max(1);
+                        x = 0;
+                        run = 0;
+                    }
+                    '!' => break 'lines,
+                    c if c.is_whitespace() => {}
+                    c => {
+                        return Err(PatternError::UnexpectedChar {
+                            line: number,
+                            column: column + 1,
+                            c,
+                        })
+                    }
+                }
+            }
+        }
+
+        // the declared box is kept, so blank margins survive a round trip
+        let (width, height, rule) = header.ok_or(PatternError::Empty)?;
+        Ok(Self {
+            name,
+            width,
+            height,
+            rule,
+            cells,
+        })
+    }
+
+    /// Parses plaintext `.cells` patterns: `!` comments, then rows of `.`
+    /// for dead cells and `O` for live ones.
+    pub fn parse_plaintext(text: &str) -> Result<Self, PatternError> {
+        let mut name = None;
+        let mut cells = vec![];
+        let mut rows = 0;
+        let mut width = 0;
+
+        for (i, line) in text.trim_end().lines().enumerate() {
+            let line = line.trim_end();
+            if let Some(comment) = line.strip_prefix('!') {
+                if let Some(n) = comment.strip_prefix("Name:") {
+                    name = Some(n.trim().to_string());
+                }
+                continue;
+            }
+
+            for (column, c) in line.chars().enumerate() {
+                match c {
+                    '.' => {}
+                    'O' | '*' => cells.push((column, rows)),
+                    c => {
+                        return Err(PatternError::UnexpectedChar {
+                            line: i + 1,
+                            column: column + 1,
+                            c,
+                        })
+                    }
+                }
+            }
+            width = width.max(line.len());
+            rows += 1;
+        }
+
+        if rows == 0 {
+            return Err(PatternError::Empty);
+        }
+
+        Ok(Self {
+            name,
+            width,
+            height: rows,
+            rule: None,
+            cells,
+        })
+    }
+
+    /// Parses Life 1.06 files: a `#Life 1.06` line, then one `x y` pair per
+    /// live cell, which may be negative.
+    pub fn parse_life_106(text: &str) -> Result<Self, PatternError> {
+        let mut cells = vec![];
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.starts_with('#') || line.is_empty() {
+                continue;
+            }
+
+            let mut coordinates = line.split_whitespace().map(str::parse::<i64>);
+            match (coordinates.next(), coordinates.next(), coordinates.next()) {
+                (Some(Ok(x)), Some(Ok(y)), None) => cells.push((x, y)),
+                _ => {
+                    return Err(PatternError::InvalidCoordinates {
+                        line: i + 1,
+                        text: line.to_string(),
+                    })
+                }
+            }
+        }
+
+        if cells.is_empty() {
+            return Err(PatternError::Empty);
+        }
+        Ok(Self::from_cells(None, None, &cells))
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Live cells relative to the top left of the bounding box.
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+
+    /// Live cells after mirroring the pattern left to right if asked,
+    /// turning it, and moving its top left corner to `(x, y)`.
+    pub fn placed(
+        &self,
+        x: i64,
+        y: i64,
+        rotation: Rotation,
+        mirror: bool,
+    ) -> impl Iterator<Item = (i64, i64)> + '_ {
+        let (w, h) = (self.width as i64, self.height as i64);
+        self.cells.iter().map(move |&(px, py)| {
+            let (px, py) = match mirror {
+                true => (w - 1 - px as i64, py as i64),
+                false => (px as i64, py as i64),
+            };
+            let (rx, ry) = match rotation {
+                Rotation::None => (px, py),
+                Rotation::Quarter => (h - 1 - py, px),
+                Rotation::Half => (w - 1 - px, h - 1 - py),
+                Rotation::ThreeQuarters => (py, w - 1 - px),
+            };
+            (x + rx, y + ry)
+        })
+    }
+
+    /// Writes the pattern as RLE, with the rule in the header if it has one.
+    pub fn to_rle(&self) -> String {
+        let mut out = String::new();
+        if let Some(name) = &self.name {
+            out += &format!("#N {name}\n");
+        }
+        out += &format!("x = {}, y = {}", self.width, self.height);
+        if let Some(rule) = &self.rule {
+            out += &format!(", rule = {rule}");
+        }
+        out.push('\n');
+
+        let mut alive = vec![false; self.width * self.height];
+        for &(x, y) in &self.cells {
+            alive[y * self.width + x] = true;
+        }
+
+        // runs of (count, tag), leaving out dead cells at the end of a row
+        // and merging blank rows into the following `$`
+        let mut runs: Vec<(usize, char)> = vec![];
+        let mut push = |count: usize, tag: char| match runs.last_mut() {
+            Some((n, last)) if *last == tag => *n += count,
+            _ => runs.push((count, tag)),
+        };
+        for (y, row) in alive.chunks(self.width.max(1)).enumerate() {
+            if y > 0 {
+                push(1, '$');
+            }
+            let end = row.iter().rposition(|&c| c).map_or(0, |i| i + 1);
+            for &cell in &row[..end] {
+                push(1, if cell { 'o' } else { 'b' });
+            }
+        }
+        while runs.last().is_some_and(|&(_, tag)| tag == '$') {
+            runs.pop();
+        }
+        runs.push((1, '!'));
+
+        // lines of at most 70 characters, without splitting a run
+        let mut line = String::new();
+        for (count, tag) in runs {
+            let run = match count {
+                1 => tag.to_string(),
+                n => format!("{n}{tag}"),
+            };
+            if line.len() + run.len() > 70 {
+                out += &line;
+                out.push('\n');
+                line.clear();
+            }
+            line += &run;
+        }
+        out += &line;
+        out.push('\n');
+        out
+    }
+
+    /// The width and height after turning.
+    pub fn rotated_size(&self, rotation: Rotation) -> (usize, usize) {
+        match rotation {
+            Rotation::None | Rotation::Half => (self.width, self.height),
+            Rotation::Quarter | Rotation::ThreeQuarters => (self.height, self.width),
+        }
+    }
+}
+
+/// Parses `x = 3, y = 3, rule = B3/S23` into the bounds and the rule.
+fn parse_rle_header(
+    line: &str,
+    number: usize,
+) -> Result<(usize, usize, Option<Rule>), PatternError> {
+    let invalid = || PatternError::InvalidHeader {
+        line: number,
+        header: line.to_string(),
+    };
+
+    // the rule comes last and may itself contain commas
+    let (bounds, rule) = match line.find("rule") {
+        Some(i) => (&line[..i], Some(&line[i + "rule".len()..])),
+        None => (line, None),
+    };
+
+    let (mut width, mut height) = (None, None);
+    for field in bounds.split(',').filter(|field| !field.trim().is_empty()) {
+        let (key, value) = field.split_once('=').ok_or_else(invalid)?;
+        let value = value.trim().parse().map_err(|_| invalid())?;
+        match key.trim() {
+            "x" => width = Some(value),
+            "y" => height = Some(value),
+            _ => return Err(invalid()),
+        }
+    }
+
+    let rule = match rule {
+        Some(rule) => {
+            let rule = rule.trim().strip_prefix('=').ok_or_else(invalid)?;
+            // drop any bounded grid suffix, e.g. B3/S23:T64,64
+            let rule = rule.split(':').next().unwrap_or_default();
+            match Rule::parse(rule) {
+                Ok(rule) => Some(rule),
+                Err(error) => {
+                    return Err(PatternError::InvalidRule {
+                        line: number,
+                        error,
+                    })
+                }
+            }
+        }
+        None => None,
+    };
+
+    match (width, height) {
+        (Some(width), Some(height)) => Ok((width, height, rule)),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::{self, LIBRARY};
+
+    const GLIDER: [(usize, usize); 5] = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+
+    #[test]
+    fn library_entries_round_trip_through_rle() {
+        for entry in LIBRARY {
+            let pattern = entry.pattern();
+            assert_eq!(
+                Pattern::parse(&pattern.to_rle()),
+                Ok(pattern),
+                "{}",
+                entry.name
+            );
+        }
+    }
+
+    #[test]
+    fn parses_the_rle_header() {
+        let pattern =
+            Pattern::parse("#N Glider\nx = 4, y = 3, rule = B36/S23:T64,64\nbo$2bo$3o!").unwrap();
+        assert_eq!(pattern.name.as_deref(), Some("Glider"));
+        assert_eq!((pattern.width, pattern.height), (4, 3));
+        assert_eq!(pattern.rule, Some(Rule::HIGHLIFE));
+        assert_eq!(pattern.cells(), GLIDER);
+
+        let pattern = Pattern::parse("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+        assert_eq!(pattern.rule, None);
+    }
+
+    #[test]
+    fn parses_every_format() {
+        let plaintext = Pattern::parse("!Name: Glider\n.O.\n..O\nOOO\n").unwrap();
+        assert_eq!(plaintext.name.as_deref(), Some("Glider"));
+        assert_eq!(plaintext.cells(), GLIDER);
+
+        let life_106 = Pattern::parse("#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n").unwrap();
+        assert_eq!(life_106.cells(), GLIDER);
+
+        let rle = library::find("glider").unwrap().pattern();
+        assert_eq!(rle.cells(), GLIDER);
+    }
+
+    #[test]
+    fn places_rotated_and_mirrored() {
+        // an L three wide and two high
+        let pattern = Pattern::parse("x = 3, y = 2\no$3o!").unwrap();
+        let placed = |rotation, mirror| {
+            let mut cells: Vec<_> = pattern.placed(10, 20, rotation, mirror).collect();
+            cells.sort_unstable();
+            cells
+        };
+
+        assert_eq!(
+            placed(Rotation::None, false),
+            [(10, 20), (10, 21), (11, 21), (12, 21)]
+        );
+        assert_eq!(
+            placed(Rotation::Quarter, false),
+            [(10, 20), (10, 21), (10, 22), (11, 20)]
+        );
+        assert_eq!(
+            placed(Rotation::Half, false),
+            [(10, 20), (11, 20), (12, 20), (12, 21)]
+        );
+        assert_eq!(
+            placed(Rotation::ThreeQuarters, false),
+            [(10, 22), (11, 20), (11, 21), (11, 22)]
+        );
+        assert_eq!(
+            placed(Rotation::None, true),
+            [(10, 21), (11, 21), (12, 20), (12, 21)]
+        );
+        assert_eq!(pattern.rotated_size(Rotation::Quarter), (2, 3));
+    }
+
+    #[test]
+    fn reports_where_parsing_failed() {
+        assert_eq!(Pattern::parse(" \n\n"), Err(PatternError::Empty));
+        assert_eq!(Pattern::parse("#Life 1.06\n"), Err(PatternError::Empty));
+        assert_eq!(
+            Pattern::parse("#N Glider\nx = 3\nbo$2bo$3o!"),
+            Err(PatternError::InvalidHeader {
+                line: 2,
+                header: String::from("x = 3"),
+            })
+        );
+        assert!(matches!(
+            Pattern::parse("x = 1, y = 1, rule = B3/S23/Q\no!"),
+            Err(PatternError::InvalidRule { line: 1, .. })
+        ));
+        assert_eq!(
+            Pattern::parse("x = 2, y = 1\noz!"),
+            Err(PatternError::UnexpectedChar {
+                line: 2,
+                column: 2,
+                c: 'z',
+            })
+        );
+        assert_eq!(
+            Pattern::parse(".O\n.x\n"),
+            Err(PatternError::UnexpectedChar {
+                line: 2,
+                column: 2,
+                c: 'x',
+            })
+        );
+        assert_eq!(
+            Pattern::parse("#Life 1.06\n1 2\n3\n"),
+            Err(PatternError::InvalidCoordinates {
+                line: 3,
+                text: String::from("3"),
+            })
+        );
+        assert_eq!(
+            Pattern::parse("x = 2, y = 1\n3o!"),
+            Err(PatternError::OutsideBounds {
+                line: 2,
+                width: 2,
+                height: 1,
+            })
+        );
+        assert_eq!(
+            Pattern::parse("x = 2, y = 1\noz!").unwrap_err().to_string(),
+            "line 2, column 2: unexpected 'z'"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A fixed set of worker threads that boards hand their strips to.
+///
+/// The threads are started once and wait for work, so stepping doesn't pay
+/// for spawning them every generation. Dropping the pool lets them finish
+/// what they have and joins them.
+pub struct Pool {
+    jobs: Option<mpsc::Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Pool {
+    pub fn new(threads: usize) -> Self {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let queue = Arc::new(Mutex::new(queue));
+
+        let workers = (0..threads.max(1))
+            .map(|i| {
+                let queue = queue.clone();
+                thread::Builder::new()
+                    .name(format!("automata-worker-{i}"))
+                    .spawn(move || loop {
+                        // the lock is released before the job runs
+                        let job = queue.lock().unwrap().recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => return,
+                        }
+                    })
+                    .expect("couldn't start a worker thread")
+            })
+            .collect();
+
+        Self {
+            jobs: Some(jobs),
+            workers,
+        }
+    }
+
+    /// One thread for every core but the caller's.
+    pub fn for_cores() -> Self {
+        let cores = thread::available_parallelism().map_or(1, |n| n.get());
+        Self::new(cores.saturating_sub(1))
+    }
+
+    pub fn threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Runs every task but the last on the workers and the last on the
+    /// calling thread, and waits for them all, returning their results in
+    /// the order the tasks were given.
+    ///
+    /// Panics if a task panics.
+    pub fn map<T, F>(&self, tasks: impl IntoIterator<Item = F>) -> Vec<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let mut tasks = tasks.into_iter().peekable();
+        let (results, finished) = mpsc::channel();
+        let mut count = 0;
+        let mut last = None;
+        while let Some(task) = tasks.next() {
+            if tasks.peek().is_none() {
+                last = Some(task);
+                break;
+            }
+            let i = count;
+            let results = results.clone();
+            let job: Job = Box::new(move || {
+                let _ = results.send((i, task()));
+            });
+            self.jobs
+                .as_ref()
+                .expect("the pool is only shut down when dropped")
+                .send(job)
+                .expect("every worker thread has stopped");
+            count += 1;
+        }
+        drop(results);
+
+        let mut out: Vec<Option<T>> = (0..count).map(|_| None).collect();
+        out.push(last.map(|task| task()));
+        for _ in 0..count {
+            // a panicking task drops its sender without sending
+            let (i, result) = finished.recv().expect("a worker task panicked");
+            out[i] = Some(result);
+        }
+        out.into_iter().flatten().collect()
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        drop(self.jobs.take());
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::f32::consts::TAU;
+
+/// A window the taps are multiplied by, tapering them to zero at the ends.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Window {
+    Hann,
+    Blackman,
+    /// `beta` trades the width of the main lobe for lower side lobes, from
+    /// a rectangle at 0 to about Blackman at 8.6.
+    Kaiser {
+        beta: f32,
+    },
+}
+
+/// What a kernel's phase response is turned into, keeping its magnitude.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// As little delay as possible, for kernels that respond right away.
+    Minimum,
+    /// Symmetric taps, delaying every frequency by the same amount, half
+    /// the kernel's length. The zero phase response is twice as long as the
+    /// kernel and gets cut down to fit, which smooths the magnitude out.
+    Linear,
+}
+
+/// Floors magnitudes before taking their log, about -140 dB.
+const MIN_MAGNITUDE: f32 = 1e-7;
+
+/// The stages run on a kernel's taps after mapping, in the order they are
+/// listed. Each is skipped when it is `None` or empty.
+///
+/// Removing DC is left to `finish`, for after the kernel is normalized,
+/// since taps that sum to zero leave DC gain normalization nothing to go
+/// on.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Chain {
+    pub window: Option<Window>,
+    /// Convolved with the taps, centred on them, to smooth them out.
+    pub smoothing: Vec<f32>,
+    pub phase: Option<Phase>,
+    /// Subtracts the mean in `finish`, so the kernel lets no DC through.
+    pub remove_dc: bool,
+}
+
+impl Chain {
+    /// The stages run before normalizing.
+    pub fn apply(&self, taps: &mut [f32]) {
+        if let Some(window) = self.window {
+            let n = taps.len();
+            for (i, tap) in taps.iter_mut().enumerate() {
+                *tap *= window.at(i, n);
+            }
+        }
+
+        if !self.smoothing.is_empty() {
+            let smoothed = convolve(taps, &self.smoothing);
+            taps.copy_from_slice(&smoothed);
+        }
+
+        match self.phase {
+            Some(Phase::Minimum) => minimum_phase(taps),
+            Some(Phase::Linear) => linear_phase(taps),
+            None => {}
+        }
+    }
+
+    /// The stages run after normalizing, which is only removing DC.
+    pub fn finish(&self, taps: &mut [f32]) {
+        if self.remove_dc && !taps.is_empty() {
+            let mean = taps.iter().sum::<f32>() / taps.len() as f32;
+            for tap in taps {
+                *tap -= mean;
+            }
+        }
+    }
+}
+
+impl Window {
+    /// The window's value at tap `i` of `n`.
+    pub fn at(&self, i: usize, n: usize) -> f32 {
+        if n < 2 {
+            return 1.0;
+        }
+        let x = i as f32 / (n - 1) as f32;
+        match self {
+            Window::Hann => 0.5 - 0.5 * (TAU * x).cos(),
+            Window::Blackman => 0.42 - 0.5 * (TAU * x).cos() + 0.08 * (2.0 * TAU * x).cos(),
+            Window::Kaiser { beta } => {
+                let r = 2.0 * x - 1.0;
+                bessel_i0(beta * (1.0 - r * r).max(0.0).sqrt()) / bessel_i0(*beta)
+            }
+        }
+    }
+}
+
+/// The zeroth order modified Bessel function of the first kind, summed
+/// until the terms stop mattering.
+fn bessel_i0(x: f32) -> f32 {
+    let (mut sum, mut term) = (1.0, 1.0);
+    let half = x / 2.0;
+    for k in 1..64 {
+        term *= (half / k as f32).powi(2);
+        sum += term;
+        if term < sum * 1e-9 {
+            break;
+        }
+    }
+    sum
+}
+
+/// `taps` convolved with `kernel`, cut back to the length of `taps` around
+/// the kernel's middle.
+fn convolve(taps: &[f32], kernel: &[f32]) -> Vec<f32> {
+    let middle = kernel.len() / 2;
+    (0..taps.len())
+        .map(|i| {
+            kernel
+                .iter()
+                .enumerate()
+                .filter_map(|(j, k)| {
+                    let at = (i + middle).checked_sub(j)?;
+                    taps.get(at).map(|tap| tap * k)
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// Enough points that the cepstrum doesn't alias much.
+fn transform_size(taps: usize) -> usize {
+    (taps * 8).next_power_of_two()
+}
+
+/// Rebuilds `taps` with the same magnitude response and minimum phase,
+/// through the real cepstrum.
+fn minimum_phase(taps: &mut [f32]) {
+    let n = transform_size(taps.len());
+    let spectrum = dft(&pad(taps, n), false);
+
+    let log_magnitude: Vec<(f32, f32)> = spectrum
+        .iter()
+        .map(|(re, im)| (re.hypot(*im).max(MIN_MAGNITUDE).ln(), 0.0))
+        .collect();
+    let cepstrum = dft(&log_magnitude, true);
+
+    // folding the anticausal half onto the causal half leaves the minimum
+    // phase sequence with the same magnitude
+    let folded: Vec<(f32, f32)> = (0..n)
+        .map(|i| match i {
+            0 => cepstrum[0],
+            i if i < n / 2 => (2.0 * cepstrum[i].0, 0.0),
+            i if i == n / 2 => cepstrum[i],
+            _ => (0.0, 0.0),
+        })
+        .collect();
+
+    let exponential: Vec<(f32, f32)> = dft(&folded, false)
+        .into_iter()
+        .map(|(re, im)| {
+            let magnitude = re.exp();
+            (magnitude * im.cos(), magnitude * im.sin())
+        })
+        .collect();
+    for (tap, (re, _)) in taps.iter_mut().zip(dft(&exponential, true)) {
+        *tap = re;
+    }
+}
+
+/// Rebuilds `taps` with the same magnitude response and zero phase, then
+/// delays them to start at the first tap.
+fn linear_phase(taps: &mut [f32]) {
+    let n = transform_size(taps.len());
+    let magnitude: Vec<(f32, f32)> = dft(&pad(taps, n), false)
+        .iter()
+        .map(|(re, im)| (re.hypot(*im), 0.0))
+        .collect();
+    let zero_phase = dft(&magnitude, true);
+
+    let middle = taps.len() / 2;
+    for (i, tap) in taps.iter_mut().enumerate() {
+        // negative times wrap round to the end
+        let at = (i + n - middle) % n;
+        *tap = zero_phase[at].0;
+    }
+}
+
+fn pad(taps: &[f32], n: usize) -> Vec<(f32, f32)> {
+    (0..n)
+        .map(|i| (taps.get(i).copied().unwrap_or(0.0), 0.0))
+        .collect()
+}
+
+/// A plain discrete Fourier transform, scaled by `1 / n` when `inverse`.
+/// Kernels are short enough that the quadratic cost doesn't matter.
+fn dft(input: &[(f32, f32)], inverse: bool) -> Vec<(f32, f32)> {
+    let n = input.len();
+    let sign = match inverse {
+        true => 1.0,
+        false => -1.0,
+    };
+    let scale = match inverse {
+        true => 1.0 / n as f32,
+        false => 1.0,
+    };
+
+    // every angle is a multiple of one turn over n
+    let twiddles: Vec<(f32, f32)> = (0..n)
+        .map(|m| (sign * TAU * m as f32 / n as f32).sin_cos())
+        .collect();
+
+    (0..n)
+        .map(|k| {
+            let (re, im) = input
+                .iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(re, im), (j, (a, b))| {
+                    let (sin, cos) = twiddles[(j * k) % n];
+                    (re + a * cos - b * sin, im + a * sin + b * cos)
+                });
+            (re * scale, im * scale)
+        })
+        .collect()
+}
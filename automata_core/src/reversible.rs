@@ -0,0 +1,506 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::{
+    automaton::Automaton,
+    board::{BitBoard, Board},
+    neighborhood::Neighborhood,
+    pool::Pool,
+    rule::Rule,
+    seeder,
+    topology::Topology,
+};
+
+/// Engines whose every step can be undone exactly, without keeping the
+/// generations they came from.
+pub trait Reversible: Automaton {
+    /// Goes back one generation, undoing `step`.
+    fn step_back(&mut self);
+}
+
+/// Fredkin's second-order construction over a Life-like rule: the next
+/// generation is the rule applied to the current one, XORed with the
+/// previous one. Knowing two generations in a row fixes both the one after
+/// and the one before, so any rule becomes reversible.
+///
+/// Generations rules run without their refractory states.
+pub struct SecondOrder {
+    board: BitBoard,
+    previous: Vec<bool>,
+    rule: Rule,
+    field: Vec<f32>,
+}
+
+impl SecondOrder {
+    pub fn new(size: usize) -> Self {
+        Self {
+            board: BitBoard::new(size),
+            previous: vec![false; size * size],
+            rule: Rule::default(),
+            field: vec![0.0; size * size],
+        }
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule.with_states(2);
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.board.set_topology(topology);
+    }
+
+    pub fn set_neighborhood(&mut self, neighborhood: Neighborhood) {
+        if *self.board.neighborhood() != neighborhood {
+            self.board.set_neighborhood(neighborhood);
+        }
+    }
+
+    /// Shares out the steps of large boards, see `BitBoard`.
+    pub fn set_pool(&mut self, pool: Option<Arc<Pool>>) {
+        self.board.set_pool(pool);
+    }
+
+    /// Random current generation with an empty one before it.
+    pub fn randomize<R: Rng>(&mut self, rng: &mut R) {
+        self.reset();
+
+        let size = self.board.size();
+        for y in 0..size {
+            for x in 0..size {
+                if rng.gen() {
+                    self.board.set(x, y, true);
+                }
+            }
+        }
+
+        self.refresh_field();
+    }
+
+    fn current(&self) -> Vec<bool> {
+        let size = self.board.size();
+        (0..size * size)
+            .map(|i| self.board.get(i % size, i / size))
+            .collect()
+    }
+
+    fn put(&mut self, cells: &[bool]) {
+        let size = self.board.size();
+        for (i, alive) in cells.iter().enumerate() {
+            self.board.set(i % size, i / size, *alive);
+        }
+    }
+
+    /// Steps the board under the plain rule and XORs the result with
+    /// `other`, leaving the answer on the board.
+    fn step_xor(&mut self, other: &[bool]) {
+        self.board.step(&self.rule);
+        let size = self.board.size();
+        for (i, flip) in other.iter().enumerate() {
+            if *flip {
+                let (x, y) = (i % size, i / size);
+                self.board.set(x, y, !self.board.get(x, y));
+            }
+        }
+    }
+
+    fn refresh_field(&mut self) {
+        let size = self.board.size();
+        for (i, cell) in self.field.iter_mut().enumerate() {
+            *cell = self.board.get(i % size, i / size) as u8 as f32;
+        }
+    }
+}
+
+impl Automaton for SecondOrder {
+    fn step(&mut self) {
+        let current = self.current();
+        let previous = std::mem::replace(&mut self.previous, current);
+        self.step_xor(&previous);
+        self.refresh_field();
+    }
+
+    fn width(&self) -> usize {
+        self.board.size()
+    }
+
+    fn height(&self) -> usize {
+        self.board.size()
+    }
+
+    fn cells(&self) -> &[f32] {
+        &self.field
+    }
+
+    fn reset(&mut self) {
+        self.board.clear();
+        self.previous.fill(false);
+        self.field.fill(0.0);
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.randomize(&mut seeder::rng(seed));
+    }
+
+    /// The cells become the current generation, with an empty one before.
+    fn load(&mut self, cells: &[(i64, i64)]) {
+        self.reset();
+        let size = self.board.size() as i64;
+        for &(x, y) in cells {
+            if (0..size).contains(&x) && (0..size).contains(&y) {
+                self.board.set(x as usize, y as usize, true);
+            }
+        }
+        self.refresh_field();
+    }
+
+    /// Only the current generation is flipped.
+    fn flip(&mut self, cells: &[(i64, i64)]) {
+        let size = self.board.size() as i64;
+        for &(x, y) in cells {
+            if (0..size).contains(&x) && (0..size).contains(&y) {
+                let (x, y) = (x as usize, y as usize);
+                self.board.set(x, y, !self.board.get(x, y));
+            }
+        }
+        self.refresh_field();
+    }
+
+    /// The previous generation and then the current one, a byte a cell.
+    fn save(&self) -> Vec<u8> {
+        self.previous
+            .iter()
+            .chain(&self.current())
+            .map(|alive| *alive as u8)
+            .collect()
+    }
+
+    fn restore(&mut self, state: &[u8]) {
+        let cells = self.previous.len();
+        if state.len() != 2 * cells {
+            return;
+        }
+
+        let state: Vec<bool> = state.iter().map(|cell| *cell != 0).collect();
+        self.previous.copy_from_slice(&state[..cells]);
+        self.put(&state[cells..]);
+        self.refresh_field();
+    }
+
+    fn population(&self) -> usize {
+        self.board.population()
+    }
+}
+
+impl Reversible for SecondOrder {
+    fn step_back(&mut self) {
+        // (previous, current) came from (rule(previous) ^ current, previous)
+        let current = self.current();
+        let previous = std::mem::take(&mut self.previous);
+        self.put(&previous);
+        self.step_xor(&current);
+        self.previous = self.current();
+        self.put(&previous);
+        self.refresh_field();
+    }
+}
+
+/// A rule for `Margolus`: a permutation of the 16 ways a 2x2 block can be
+/// filled, with bit 0 the top left cell, bit 1 the top right, bit 2 the
+/// bottom left and bit 3 the bottom right.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockRule {
+    table: [u8; 16],
+    inverse: [u8; 16],
+}
+
+impl BlockRule {
+    /// Blocks with two live cells stay as they are. The rest are inverted,
+    /// and turned half way round if they had three live cells.
+    pub const CRITTERS: BlockRule = BlockRule::build(Preset::Critters);
+    /// Fredkin and Toffoli's billiard ball machine: a lone cell moves to
+    /// the opposite corner and two cells on a diagonal bounce onto the
+    /// other diagonal.
+    pub const BILLIARD_BALL: BlockRule = BlockRule::build(Preset::BilliardBall);
+    /// Empty blocks fill and full blocks empty, everything else stays.
+    pub const TRON: BlockRule = BlockRule::build(Preset::Tron);
+
+    /// `None` unless `table` is a permutation, since only those can be
+    /// run backwards.
+    pub fn new(table: [u8; 16]) -> Option<Self> {
+        let mut inverse = [u8::MAX; 16];
+        for (block, next) in table.iter().enumerate() {
+            let slot = inverse.get_mut(*next as usize)?;
+            if *slot != u8::MAX {
+                return None;
+            }
+            *slot = block as u8;
+        }
+        Some(Self { table, inverse })
+    }
+
+    const fn build(preset: Preset) -> Self {
+        let mut table = [0; 16];
+        let mut inverse = [0; 16];
+        let mut block = 0;
+        while block < 16 {
+            let next = match preset {
+                Preset::Critters => critters(block as u8),
+                Preset::BilliardBall => billiard_ball(block as u8),
+                Preset::Tron => tron(block as u8),
+            };
+            table[block] = next;
+            inverse[next as usize] = block as u8;
+            block += 1;
+        }
+        Self { table, inverse }
+    }
+
+    pub fn next(&self, block: u8) -> u8 {
+        self.table[block as usize & 15]
+    }
+
+    pub fn previous(&self, block: u8) -> u8 {
+        self.inverse[block as usize & 15]
+    }
+}
+
+enum Preset {
+    Critters,
+    BilliardBall,
+    Tron,
+}
+
+const fn half_turn(block: u8) -> u8 {
+    (block & 1) << 3 | (block & 2) << 1 | (block & 4) >> 1 | (block & 8) >> 3
+}
+
+const fn critters(block: u8) -> u8 {
+    match block.count_ones() {
+        2 => block,
+        3 => half_turn(block ^ 15),
+        _ => block ^ 15,
+    }
+}
+
+const fn billiard_ball(block: u8) -> u8 {
+    match block {
+        0b1001 => 0b0110,
+        0b0110 => 0b1001,
+        _ if block.count_ones() == 1 => half_turn(block),
+        _ => block,
+    }
+}
+
+const fn tron(block: u8) -> u8 {
+    match block {
+        0 => 15,
+        15 => 0,
+        _ => block,
+    }
+}
+
+/// A block cellular automaton on the Margolus neighbourhood: the board is
+/// cut into 2x2 blocks, each replaced according to a `BlockRule`, with the
+/// blocks moved one cell down and to the right on every other generation.
+///
+/// The blocks wrap round a torus, so every cell is in a block on every
+/// step. Blocks can only tile an even number of cells, so on odd sizes the
+/// last row and column are left out of the board and stay empty.
+///
+/// Rules that fill empty blocks, like Critters, invert the whole board each
+/// step, so `cells` inverts it back on every other generation to keep the
+/// background still.
+pub struct Margolus {
+    size: usize,
+    rule: BlockRule,
+    cells: Vec<bool>,
+    odd: bool,
+    field: Vec<f32>,
+}
+
+impl Margolus {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            rule: BlockRule::CRITTERS,
+            cells: vec![false; size * size],
+            odd: false,
+            field: vec![0.0; size * size],
+        }
+    }
+
+    pub fn rule(&self) -> &BlockRule {
+        &self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: BlockRule) {
+        if rule != self.rule {
+            self.rule = rule;
+            self.refresh_field();
+        }
+    }
+
+    pub fn randomize<R: Rng>(&mut self, rng: &mut R) {
+        self.reset();
+        let blocks = self.blocks();
+        for y in 0..blocks {
+            for x in 0..blocks {
+                self.cells[y * self.size + x] = rng.gen();
+            }
+        }
+        self.refresh_field();
+    }
+
+    /// The width of the square the blocks tile.
+    fn blocks(&self) -> usize {
+        self.size & !1
+    }
+
+    fn apply(&mut self, table: [u8; 16]) {
+        let n = self.size;
+        let m = self.blocks();
+        let offset = self.odd as usize;
+        for y in (offset..m).step_by(2) {
+            for x in (offset..m).step_by(2) {
+                let (right, below) = ((x + 1) % m, (y + 1) % m);
+                let corners = [y * n + x, y * n + right, below * n + x, below * n + right];
+
+                let block = corners
+                    .iter()
+                    .enumerate()
+                    .fold(0, |block, (bit, i)| block | (self.cells[*i] as u8) << bit);
+                let next = table[block as usize];
+                for (bit, i) in corners.iter().enumerate() {
+                    self.cells[*i] = next & (1 << bit) != 0;
+                }
+            }
+        }
+    }
+
+    fn refresh_field(&mut self) {
+        let invert = self.odd && self.rule.next(0) == 15;
+        let (n, m) = (self.size, self.blocks());
+        for (i, (value, cell)) in self.field.iter_mut().zip(&self.cells).enumerate() {
+            let inside = i % n < m && i / n < m;
+            *value = (*cell != (invert && inside)) as u8 as f32;
+        }
+    }
+}
+
+impl Automaton for Margolus {
+    fn step(&mut self) {
+        self.apply(self.rule.table);
+        self.odd = !self.odd;
+        self.refresh_field();
+    }
+
+    fn width(&self) -> usize {
+        self.size
+    }
+
+    fn height(&self) -> usize {
+        self.size
+    }
+
+    fn cells(&self) -> &[f32] {
+        &self.field
+    }
+
+    fn reset(&mut self) {
+        self.cells.fill(false);
+        self.odd = false;
+        self.field.fill(0.0);
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.randomize(&mut seeder::rng(seed));
+    }
+
+    fn load(&mut self, cells: &[(i64, i64)]) {
+        self.reset();
+        self.flip(cells);
+    }
+
+    /// Works on the cells as shown, so flipping a cell that looks alive
+    /// kills it even while the board is inverted. Cells left out of the
+    /// blocks on odd sizes are ignored.
+    fn flip(&mut self, cells: &[(i64, i64)]) {
+        let (size, blocks) = (self.size as i64, self.blocks() as i64);
+        for &(x, y) in cells {
+            if (0..blocks).contains(&x) && (0..blocks).contains(&y) {
+                let cell = &mut self.cells[(y * size + x) as usize];
+                *cell = !*cell;
+            }
+        }
+        self.refresh_field();
+    }
+
+    /// Whether the blocks are offset, then a byte a cell.
+    fn save(&self) -> Vec<u8> {
+        std::iter::once(self.odd)
+            .chain(self.cells.iter().copied())
+            .map(|on| on as u8)
+            .collect()
+    }
+
+    fn restore(&mut self, state: &[u8]) {
+        if state.len() != self.cells.len() + 1 {
+            return;
+        }
+
+        self.odd = state[0] != 0;
+        for (cell, saved) in self.cells.iter_mut().zip(&state[1..]) {
+            *cell = *saved != 0;
+        }
+        self.refresh_field();
+    }
+
+    fn population(&self) -> usize {
+        self.field.iter().filter(|cell| **cell >= 0.5).count()
+    }
+}
+
+impl Reversible for Margolus {
+    fn step_back(&mut self) {
+        self.odd = !self.odd;
+        self.apply(self.rule.inverse);
+        self.refresh_field();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_boards_stay_empty() {
+        for rule in [BlockRule::CRITTERS, BlockRule::TRON] {
+            for size in [32, 33] {
+                let mut margolus = Margolus::new(size);
+                margolus.set_rule(rule);
+                for generation in 1..=4 {
+                    margolus.step();
+                    assert!(
+                        margolus.cells().iter().all(|cell| *cell == 0.0),
+                        "size {size}, generation {generation}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn steps_back_across_the_wrap() {
+        let mut margolus = Margolus::new(33);
+        margolus.randomize(&mut seeder::rng(1));
+        let start = margolus.save();
+        for _ in 0..5 {
+            margolus.step();
+        }
+        for _ in 0..5 {
+            margolus.step_back();
+        }
+        assert_eq!(margolus.save(), start);
+    }
+}
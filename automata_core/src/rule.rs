@@ -0,0 +1,432 @@
+use std::{fmt, str::FromStr};
+
+/// The largest neighbor count a rule can react to.
+pub const MAX_COUNT: u8 = 63;
+
+/// A Life-like totalistic rule, stored as bitmasks over the number of live
+/// neighbors (bit `n` set means `n` neighbors triggers a birth / survival).
+///
+/// Counts above 8 only come up with the larger neighborhoods, and are
+/// written as comma separated counts or ranges, e.g. `B34-45/S33-57`. A rule
+/// with any such count is written this way in both halves, and a half with
+/// a single count is read as that count if either half has a comma, a range
+/// or a 9, so `B3/S9` is 9 but `B3/S12` is 1 and 2. A lone count above 9
+/// that nothing else marks is written as a range, e.g. `B3/S12-12`.
+///
+/// Rules with more than two states are from the Generations family
+/// (`B2/S/C3` is Brian's Brain): instead of dying outright, a live cell
+/// that fails to survive counts up through `states - 2` refractory states
+/// before it is dead again. Only live cells count as neighbors, and
+/// refractory cells can't be born into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    birth: u64,
+    survival: u64,
+    states: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuleError {
+    Empty,
+    MissingSeparator,
+    InvalidDigit(char),
+    DuplicateDigit(u8),
+    CountTooLarge(u32),
+    InvalidRange(String),
+    InvalidStates(String),
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleError::Empty => write!(f, "empty rulestring"),
+            RuleError::MissingSeparator => write!(f, "expected a '/' between birth and survival"),
+            RuleError::InvalidDigit(c) => write!(f, "invalid neighbor count '{c}'"),
+            RuleError::DuplicateDigit(d) => write!(f, "neighbor count {d} given twice"),
+            RuleError::CountTooLarge(n) => {
+                write!(f, "neighbor count {n} is above the maximum of {MAX_COUNT}")
+            }
+            RuleError::InvalidRange(r) => write!(f, "invalid neighbor count range '{r}'"),
+            RuleError::InvalidStates(s) => {
+                write!(f, "invalid state count '{s}', expected a number from 2 to 255")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+impl Rule {
+    pub const CONWAY: Rule = Rule::from_counts(&[3], &[2, 3]);
+    pub const HIGHLIFE: Rule = Rule::from_counts(&[3, 6], &[2, 3]);
+    pub const SEEDS: Rule = Rule::from_counts(&[2], &[]);
+    pub const DAY_AND_NIGHT: Rule = Rule::from_counts(&[3, 6, 7, 8], &[3, 4, 6, 7, 8]);
+    pub const MAZE: Rule = Rule::from_counts(&[3], &[1, 2, 3, 4, 5]);
+    pub const LIFE_WITHOUT_DEATH: Rule = Rule::from_counts(&[3], &[0, 1, 2, 3, 4, 5, 6, 7, 8]);
+    pub const DIAMOEBA: Rule = Rule::from_counts(&[3, 5, 6, 7, 8], &[5, 6, 7, 8]);
+    pub const MORLEY: Rule = Rule::from_counts(&[3, 6, 8], &[2, 4, 5]);
+    pub const ANNEAL: Rule = Rule::from_counts(&[4, 6, 7, 8], &[3, 5, 6, 7, 8]);
+    pub const BRIANS_BRAIN: Rule = Rule::from_counts(&[2], &[]).with_states(3);
+    pub const STAR_WARS: Rule = Rule::from_counts(&[2], &[3, 4, 5]).with_states(4);
+    pub const FROGS: Rule = Rule::from_counts(&[3, 4], &[1, 2]).with_states(3);
+    pub const STICKS: Rule = Rule::from_counts(&[2], &[3, 4, 5, 6]).with_states(6);
+
+    pub const fn from_counts(birth: &[u8], survival: &[u8]) -> Self {
+        Self {
+            birth: mask(birth),
+            survival: mask(survival),
+            states: 2,
+        }
+    }
+
+    pub const fn with_states(self, states: u8) -> Self {
+        Self { states, ..self }
+    }
+
+    /// Parses a rulestring in either B/S notation (`B36/S23`) or the older
+    /// S/B notation (`23/36`), optionally followed by a Generations state
+    /// count (`B2/S345/C4` or `345/2/4`).
+    pub fn parse(s: &str) -> Result<Self, RuleError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(RuleError::Empty);
+        }
+
+        let (first, rest) = s.split_once('/').ok_or(RuleError::MissingSeparator)?;
+        let (second, states) = match rest.split_once('/') {
+            Some((second, states)) => (second, parse_states(states)?),
+            None => (rest, 2),
+        };
+
+        let (birth, survival) = match (prefix(first), prefix(second)) {
+            (Some('b'), Some('s')) | (Some('b'), None) => (&first[1..], strip(second)),
+            (Some('s'), Some('b')) | (Some('s'), None) => (strip(second), &first[1..]),
+            (None, Some('s')) => (first, &second[1..]),
+            (None, Some('b')) => (&second[1..], first),
+            // plain digits are the traditional survival/birth ordering
+            _ => (second, first),
+        };
+
+        let wide = is_wide(birth) || is_wide(survival);
+        Ok(Self {
+            birth: parse_counts(birth, wide)?,
+            survival: parse_counts(survival, wide)?,
+            states,
+        })
+    }
+
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    pub fn born(&self, neighbors: u8) -> bool {
+        neighbors <= MAX_COUNT && self.birth & (1 << neighbors) != 0
+    }
+
+    pub fn survives(&self, neighbors: u8) -> bool {
+        neighbors <= MAX_COUNT && self.survival & (1 << neighbors) != 0
+    }
+
+    /// The state a cell moves to from refractory state `state`.
+    pub fn decay(&self, state: u8) -> u8 {
+        match state.saturating_add(1) < self.states {
+            true => state + 1,
+            false => 0,
+        }
+    }
+
+    /// How much a cell in `state` contributes to a kernel: 1 for live cells,
+    /// fading linearly through the refractory states, 0 for dead cells.
+    pub fn weight(&self, state: u8) -> f32 {
+        match state {
+            0 => 0.0,
+            s if s >= self.states => 0.0,
+            s => (self.states - s) as f32 / (self.states - 1) as f32,
+        }
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::CONWAY
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rule::parse(s)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let wide = (self.birth | self.survival) >= 1 << 9;
+        let mut birth = counts(self.birth, wide);
+        let mut survival = counts(self.survival, wide);
+        if wide && !is_wide(&birth) && !is_wide(&survival) {
+            // each half is empty or a single count, which would read as digits
+            for half in [&mut birth, &mut survival] {
+                if half.len() > 1 {
+                    *half = format!("{half}-{half}");
+                }
+            }
+        }
+
+        write!(f, "B{birth}/S{survival}")?;
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        Ok(())
+    }
+}
+
+/// The counts in `mask` as digits, or as a list of counts and ranges if
+/// `wide`.
+fn counts(mask: u64, wide: bool) -> String {
+    let mut out = String::new();
+    if !wide {
+        for n in 0..9 {
+            if mask & (1 << n) != 0 {
+                out += &n.to_string();
+            }
+        }
+        return out;
+    }
+
+    let mut first = true;
+    let mut n = 0;
+    while n <= MAX_COUNT {
+        if mask & (1 << n) == 0 {
+            n += 1;
+            continue;
+        }
+        let start = n;
+        while n < MAX_COUNT && mask & (1 << (n + 1)) != 0 {
+            n += 1;
+        }
+
+        if !first {
+            out += ",";
+        }
+        match start == n {
+            true => out += &n.to_string(),
+            false => out += &format!("{start}-{n}"),
+        }
+        first = false;
+        n += 1;
+    }
+    out
+}
+
+/// Whether a half of a rulestring can only be a list of wide counts.
+fn is_wide(counts: &str) -> bool {
+    counts.contains([',', '-', '9'])
+}
+
+const fn mask(counts: &[u8]) -> u64 {
+    let mut out = 0;
+    let mut i = 0;
+    while i < counts.len() {
+        out |= 1 << counts[i];
+        i += 1;
+    }
+    out
+}
+
+fn prefix(part: &str) -> Option<char> {
+    part.chars()
+        .next()
+        .map(|c| c.to_ascii_lowercase())
+        .filter(|c| *c == 'b' || *c == 's')
+}
+
+fn strip(part: &str) -> &str {
+    match prefix(part) {
+        Some(_) => &part[1..],
+        None => part,
+    }
+}
+
+fn parse_states(states: &str) -> Result<u8, RuleError> {
+    let digits = match states.chars().next().map(|c| c.to_ascii_lowercase()) {
+        Some('c') | Some('g') => &states[1..],
+        _ => states,
+    };
+    match digits.parse::<u8>() {
+        Ok(n) if n >= 2 => Ok(n),
+        _ => Err(RuleError::InvalidStates(states.to_string())),
+    }
+}
+
+fn parse_counts(counts: &str, wide: bool) -> Result<u64, RuleError> {
+    if wide && !counts.is_empty() {
+        return parse_count_list(counts);
+    }
+
+    let mut out = 0;
+    for c in counts.chars() {
+        let n = match c.to_digit(10) {
+            Some(n) if n <= 8 => n as u8,
+            _ => return Err(RuleError::InvalidDigit(c)),
+        };
+        if out & (1 << n) != 0 {
+            return Err(RuleError::DuplicateDigit(n));
+        }
+        out |= 1 << n;
+    }
+    Ok(out)
+}
+
+/// Parses the long form used by larger neighborhoods, e.g. `2,4,10-12`.
+fn parse_count_list(counts: &str) -> Result<u64, RuleError> {
+    let count = |s: &str| -> Result<u8, RuleError> {
+        let n: u32 = s
+            .trim()
+            .parse()
+            .map_err(|_| RuleError::InvalidRange(s.to_string()))?;
+        match n <= MAX_COUNT as u32 {
+            true => Ok(n as u8),
+            false => Err(RuleError::CountTooLarge(n)),
+        }
+    };
+
+    let mut out = 0;
+    for part in counts.split(',') {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (count(start)?, count(end)?),
+            None => (count(part)?, count(part)?),
+        };
+        if start > end {
+            return Err(RuleError::InvalidRange(part.to_string()));
+        }
+
+        for n in start..=end {
+            if out & (1 << n) != 0 {
+                return Err(RuleError::DuplicateDigit(n));
+            }
+            out |= 1 << n;
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(rule: Rule) {
+        let text = rule.to_string();
+        assert_eq!(Rule::parse(&text), Ok(rule), "{text}");
+    }
+
+    #[test]
+    fn presets_round_trip() {
+        for (rule, text) in [
+            (Rule::CONWAY, "B3/S23"),
+            (Rule::HIGHLIFE, "B36/S23"),
+            (Rule::SEEDS, "B2/S"),
+            (Rule::DAY_AND_NIGHT, "B3678/S34678"),
+            (Rule::MAZE, "B3/S12345"),
+            (Rule::LIFE_WITHOUT_DEATH, "B3/S012345678"),
+            (Rule::DIAMOEBA, "B35678/S5678"),
+            (Rule::MORLEY, "B368/S245"),
+            (Rule::ANNEAL, "B4678/S35678"),
+            (Rule::BRIANS_BRAIN, "B2/S/C3"),
+            (Rule::STAR_WARS, "B2/S345/C4"),
+            (Rule::FROGS, "B34/S12/C3"),
+            (Rule::STICKS, "B2/S3456/C6"),
+        ] {
+            assert_eq!(rule.to_string(), text);
+            round_trip(rule);
+        }
+    }
+
+    #[test]
+    fn parses_every_notation() {
+        let highlife = Ok(Rule::HIGHLIFE);
+        assert_eq!(Rule::parse("B36/S23"), highlife);
+        assert_eq!(Rule::parse("b36/s23"), highlife);
+        assert_eq!(Rule::parse("S23/B36"), highlife);
+        assert_eq!(Rule::parse("B36/23"), highlife);
+        assert_eq!(Rule::parse("36/S23"), highlife);
+        assert_eq!(Rule::parse("  23/36 "), highlife);
+
+        let star_wars = Ok(Rule::STAR_WARS);
+        assert_eq!(Rule::parse("B2/S345/C4"), star_wars);
+        assert_eq!(Rule::parse("B2/S345/g4"), star_wars);
+        assert_eq!(Rule::parse("345/2/4"), star_wars);
+
+        assert_eq!(
+            Rule::parse("B34-45/S33-57"),
+            Ok(Rule::from_counts(
+                &(34..=45).collect::<Vec<_>>(),
+                &(33..=57).collect::<Vec<_>>()
+            ))
+        );
+        assert_eq!(
+            Rule::parse("B2,4,10-12/S"),
+            Ok(Rule::from_counts(&[2, 4, 10, 11, 12], &[]))
+        );
+    }
+
+    #[test]
+    fn reports_every_error() {
+        for (text, error) in [
+            ("", RuleError::Empty),
+            ("   ", RuleError::Empty),
+            ("B3S23", RuleError::MissingSeparator),
+            ("B3/S2x", RuleError::InvalidDigit('x')),
+            ("B33/S23", RuleError::DuplicateDigit(3)),
+            ("B3,3/S", RuleError::DuplicateDigit(3)),
+            ("B2-4,3/S", RuleError::DuplicateDigit(3)),
+            ("B3/S9,64", RuleError::CountTooLarge(64)),
+            ("B3/S2-70", RuleError::CountTooLarge(70)),
+            ("B5-3/S", RuleError::InvalidRange(String::from("5-3"))),
+            ("B3,x/S", RuleError::InvalidRange(String::from("x"))),
+            ("B3/S23/C1", RuleError::InvalidStates(String::from("C1"))),
+            ("B3/S/C300", RuleError::InvalidStates(String::from("C300"))),
+            ("B3/S23/Cx", RuleError::InvalidStates(String::from("Cx"))),
+        ] {
+            assert_eq!(Rule::parse(text), Err(error), "{text:?}");
+        }
+        assert_eq!(
+            RuleError::CountTooLarge(64).to_string(),
+            "neighbor count 64 is above the maximum of 63"
+        );
+    }
+
+    #[test]
+    fn wide_counts_round_trip() {
+        for (birth, survival) in [
+            (&[3][..], &[9][..]),
+            (&[3], &[12]),
+            (&[12], &[]),
+            (&[], &[10]),
+            (&[10], &[12]),
+            (&[3, 4], &[19]),
+            (&[10, 12], &[9]),
+            (&[34, 35, 36, 40], &[0, 33, 34, 35, 63]),
+        ] {
+            round_trip(Rule::from_counts(birth, survival));
+        }
+        assert_eq!(Rule::from_counts(&[3], &[9]).to_string(), "B3/S9");
+        assert_eq!(Rule::from_counts(&[3], &[12]).to_string(), "B3/S12-12");
+        assert_eq!(Rule::from_counts(&[3, 4], &[12]).to_string(), "B3-4/S12");
+    }
+
+    #[test]
+    fn parses_wide_counts_marked_by_the_other_half() {
+        assert_eq!(
+            Rule::parse("B10,12/S9"),
+            Ok(Rule::from_counts(&[10, 12], &[9]))
+        );
+        assert_eq!(
+            Rule::parse("B12/S2-3"),
+            Ok(Rule::from_counts(&[12], &[2, 3]))
+        );
+        // without anything wide, digits are single counts
+        assert_eq!(Rule::parse("B12/S3"), Ok(Rule::from_counts(&[1, 2], &[3])));
+    }
+}
@@ -0,0 +1,191 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use crate::pattern::{Pattern, Rotation};
+
+pub type SeedRng = Xoshiro256PlusPlus;
+
+/// The generator behind every random board: Xoshiro256++, with the 64 bit
+/// seed expanded by SplitMix64.
+///
+/// Unlike `SmallRng`, whose algorithm depends on the platform and the rand
+/// version, this gives the same numbers everywhere, so a saved seed brings
+/// back the same boards on another machine. Draws that go through `usize`
+/// are not portable, since rand samples them at the platform's width, so
+/// ranges of cell indices are sampled as `u32`.
+pub fn rng(seed: u64) -> SeedRng {
+    Xoshiro256PlusPlus::seed_from_u64(seed)
+}
+
+/// Symmetry groups for soups, named as in the usual soup searches.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Symmetry {
+    #[default]
+    None,
+    /// Unchanged by a half turn.
+    C2,
+    /// Unchanged by quarter turns.
+    C4,
+    /// Unchanged by mirroring left to right and top to bottom.
+    D4,
+    /// Unchanged by quarter turns and mirroring.
+    D8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Noise {
+    /// Every cell is independent.
+    White,
+    /// Smooth gradient noise with features about `scale` cells across, so
+    /// live cells come in clumps.
+    Perlin { scale: f32 },
+}
+
+/// Builds starting boards.
+///
+/// A soup of side `soup` is placed in the middle of the board, or covers
+/// the whole board when `soup` is 0. Cells are ranked by the noise and the
+/// top `density` of them are made alive, so the density is exact whatever
+/// the noise. Symmetric soups are made by giving every cell the noise of
+/// the first cell of its orbit. With a `pattern`, the pattern is placed in
+/// the middle instead.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Seeder {
+    pub density: f32,
+    pub symmetry: Symmetry,
+    pub noise: Noise,
+    pub soup: usize,
+    pub pattern: Option<Pattern>,
+}
+
+impl Default for Seeder {
+    fn default() -> Self {
+        Self {
+            density: 0.5,
+            symmetry: Symmetry::None,
+            noise: Noise::White,
+            soup: 0,
+            pattern: None,
+        }
+    }
+}
+
+impl Seeder {
+    /// The live cells for a `size` by `size` board.
+    pub fn cells<R: Rng>(&self, size: usize, rng: &mut R) -> Vec<(i64, i64)> {
+        if let Some(pattern) = &self.pattern {
+            let x = (size as i64 - pattern.width as i64) / 2;
+            let y = (size as i64 - pattern.height as i64) / 2;
+            return pattern.placed(x, y, Rotation::None, false).collect();
+        }
+
+        let n = match self.soup {
+            0 => size,
+            soup => soup.min(size),
+        };
+        let offset = ((size - n) / 2) as i64;
+
+        let noise = match self.noise {
+            Noise::White => (0..n * n).map(|_| rng.gen()).collect(),
+            Noise::Perlin { scale } => perlin(n, scale, rng),
+        };
+
+        // each cell takes the noise of the first cell in its orbit
+        let values: Vec<f32> = (0..n * n)
+            .map(|i| {
+                let orbit = orbit(self.symmetry, i % n, i / n, n);
+                let (x, y) = orbit.min_by_key(|&(x, y)| (y, x)).unwrap();
+                noise[y * n + x]
+            })
+            .collect();
+
+        let alive = ((n * n) as f32 * self.density.clamp(0.0, 1.0)).round() as usize;
+        let mut ranked: Vec<usize> = (0..n * n).collect();
+        ranked.sort_by(|&a, &b| values[b].total_cmp(&values[a]));
+
+        // cells in an orbit share a value, so symmetric soups keep whole
+        // orbits and can come out a few cells over
+        let cutoff = match alive {
+            0 => return vec![],
+            alive => values[ranked[alive - 1]],
+        };
+        (0..n * n)
+            .filter(|&i| values[i] >= cutoff)
+            .map(|i| ((i % n) as i64 + offset, (i / n) as i64 + offset))
+            .collect()
+    }
+}
+
+/// Every cell `(x, y)` is sent to by the symmetry, itself included.
+fn orbit(symmetry: Symmetry, x: usize, y: usize, n: usize) -> impl Iterator<Item = (usize, usize)> {
+    let (fx, fy) = (n - 1 - x, n - 1 - y);
+    let all = [
+        (x, y),
+        (fx, fy),
+        (fy, x),
+        (y, fx),
+        (fx, y),
+        (x, fy),
+        (y, x),
+        (fy, fx),
+    ];
+    let count = match symmetry {
+        Symmetry::None => 1,
+        Symmetry::C2 => 2,
+        Symmetry::C4 => 4,
+        Symmetry::D4 | Symmetry::D8 => 8,
+    };
+    all.into_iter()
+        .enumerate()
+        .take(count)
+        // D4 skips the quarter turns
+        .filter(move |&(i, _)| symmetry != Symmetry::D4 || !matches!(i, 2 | 3 | 6 | 7))
+        .map(|(_, cell)| cell)
+}
+
+const GRADIENTS: [(f32, f32); 8] = [
+    (1.0, 0.0),
+    (-1.0, 0.0),
+    (0.0, 1.0),
+    (0.0, -1.0),
+    (FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+    (-FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+    (FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+    (-FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+];
+
+/// Gradient noise over an `n` by `n` grid with lattice points every `scale`
+/// cells, in roughly `[-0.7, 0.7]`.
+fn perlin<R: Rng>(n: usize, scale: f32, rng: &mut R) -> Vec<f32> {
+    let scale = scale.max(1.0);
+    let points = (n as f32 / scale).ceil() as usize + 2;
+    // a fixed set of directions rather than random angles keeps `sin` and
+    // `cos`, which differ between platforms, out of the noise
+    let gradients: Vec<(f32, f32)> = (0..points * points)
+        .map(|_| GRADIENTS[rng.gen_range(0..GRADIENTS.len() as u32) as usize])
+        .collect();
+
+    let fade = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+    let mut out = Vec::with_capacity(n * n);
+    for y in 0..n {
+        for x in 0..n {
+            let (px, py) = (x as f32 / scale, y as f32 / scale);
+            let (ix, iy) = (px as usize, py as usize);
+            let (tx, ty) = (px - ix as f32, py - iy as f32);
+
+            let dot = |gx: usize, gy: usize| {
+                let (dx, dy) = gradients[(iy + gy) * points + ix + gx];
+                dx * (tx - gx as f32) + dy * (ty - gy as f32)
+            };
+
+            let top = lerp(dot(0, 0), dot(1, 0), fade(tx));
+            let bottom = lerp(dot(0, 1), dot(1, 1), fade(tx));
+            out.push(lerp(top, bottom, fade(ty)));
+        }
+    }
+    out
+}
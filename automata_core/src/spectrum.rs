@@ -0,0 +1,141 @@
+use std::f32::consts::{PI, TAU};
+
+use crate::automaton::Automaton;
+
+/// Which lines of the board become bands of the spectrum.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Axis {
+    /// Rows, the bottom one lowest, like a spectrogram.
+    #[default]
+    Rows,
+    /// Columns, the left one lowest.
+    Columns,
+}
+
+/// How the bins of the spectrum are shared out between lines.
+///
+/// Lines too narrow to hold a bin of their own still get one, the next bin
+/// up, pushing the lines above them up until the bands are wide enough to
+/// catch up. So while there are at least as many bins as lines, every line
+/// controls something.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Grouping {
+    /// Every line gets as many bins as the others.
+    #[default]
+    Linear,
+    /// Every line gets as many octaves as the others, from the first bin
+    /// above DC up to Nyquist.
+    Log,
+    /// Every line gets one note of a scale, from `root` Hz up. `steps` are
+    /// the notes' semitones above the root within each octave, in order
+    /// and starting with 0.
+    Scale { root: f32, steps: Vec<u8> },
+}
+
+impl Grouping {
+    pub const MAJOR: [u8; 7] = [0, 2, 4, 5, 7, 9, 11];
+    pub const MINOR: [u8; 7] = [0, 2, 3, 5, 7, 8, 10];
+    pub const PENTATONIC: [u8; 5] = [0, 2, 4, 7, 9];
+    pub const CHROMATIC: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+    /// The line, out of `lines`, that the bin at `frequency` Hz belongs to,
+    /// with `lowest` the frequency of the first bin above DC and `nyquist`
+    /// the last.
+    fn line(&self, frequency: f32, lowest: f32, nyquist: f32, lines: usize) -> usize {
+        let last = lines.saturating_sub(1);
+        let line = match self {
+            Grouping::Linear => frequency / nyquist * lines as f32,
+            Grouping::Log if frequency < lowest => 0.0,
+            Grouping::Log => {
+                (frequency / lowest).log2() / (nyquist / lowest).log2() * last as f32 + 0.5
+            }
+            Grouping::Scale { .. } if frequency <= 0.0 => 0.0,
+            Grouping::Scale { root, steps } => {
+                let semitones = 12.0 * (frequency / root.max(f32::MIN_POSITIVE)).log2();
+                let octave = (semitones / 12.0).floor();
+                let within = semitones - 12.0 * octave;
+                // notes at or below the frequency, counting the root as the
+                // first, so anything under the root lands on line 0
+                let notes = steps.iter().filter(|step| **step as f32 <= within).count();
+                octave * steps.len().max(1) as f32 + notes as f32 - 1.0
+            }
+        };
+        (line.max(0.0) as usize).min(last)
+    }
+}
+
+/// Builds a spectrum straight from a board, without going through an
+/// impulse response: each line of the board sets the level of a band of
+/// bins, so cells act like the sliders of a graphic EQ.
+///
+/// A line's level is the mean of its cells. With `phase`, the middle of its
+/// live cells also sets the band's phase, from -π at one end of the line to
+/// π at the other. Every bin is then delayed by `delay` samples, to line up
+/// with kernels made the usual way.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Spectral {
+    pub axis: Axis,
+    pub grouping: Grouping,
+    pub phase: bool,
+    pub delay: f32,
+}
+
+impl Spectral {
+    /// Fills `bins`, the first half of the spectrum of a `fft_size` point
+    /// transform, as `(magnitude, phase)` pairs.
+    pub fn map(
+        &self,
+        automaton: &dyn Automaton,
+        sample_rate: f32,
+        fft_size: usize,
+        bins: &mut [(f32, f32)],
+    ) {
+        let (width, height) = (automaton.width(), automaton.height());
+        let (lines, length) = match self.axis {
+            Axis::Rows => (height, width),
+            Axis::Columns => (width, height),
+        };
+        let cell = |line: usize, i: usize| match self.axis {
+            Axis::Rows => automaton.cell(i, height - 1 - line),
+            Axis::Columns => automaton.cell(line, i),
+        };
+
+        let levels: Vec<(f32, f32)> = (0..lines)
+            .map(|line| {
+                let (mut sum, mut moment) = (0.0, 0.0);
+                for i in 0..length {
+                    let value = cell(line, i);
+                    sum += value;
+                    moment += value * i as f32;
+                }
+
+                let middle = match sum > 0.0 {
+                    true => moment / sum / (length as f32 - 1.0).max(1.0),
+                    false => 0.5,
+                };
+                let phase = match self.phase {
+                    true => PI * (2.0 * middle - 1.0),
+                    false => 0.0,
+                };
+                (sum / length.max(1) as f32, phase)
+            })
+            .collect();
+
+        let step = sample_rate / fft_size as f32;
+        let nyquist = sample_rate / 2.0;
+        let mut line = 0;
+        for (k, bin) in bins.iter_mut().enumerate() {
+            let frequency = k as f32 * step;
+            // lines are never skipped, only moved up
+            if lines > 0 && self.grouping.line(frequency, step, nyquist, lines) > line {
+                line += 1;
+            }
+            let (magnitude, phase) = match lines {
+                0 => (0.0, 0.0),
+                _ => levels[line],
+            };
+            let delay = -TAU * k as f32 * self.delay / fft_size as f32;
+            *bin = (magnitude, phase + delay);
+        }
+    }
+}
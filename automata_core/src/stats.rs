@@ -0,0 +1,220 @@
+use std::{
+    hint,
+    sync::atomic::{fence, AtomicU64, Ordering},
+};
+
+use crate::automaton::Automaton;
+
+/// Measurements of one generation, with cells at least half way to full
+/// counted as alive.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub width: usize,
+    pub height: usize,
+    pub population: usize,
+    /// Cells alive now that weren't in the generation measured before.
+    pub births: usize,
+    /// Cells alive in the generation measured before that aren't now.
+    pub deaths: usize,
+    /// The smallest rectangle holding every live cell, as the inclusive
+    /// `(left, top, right, bottom)`.
+    pub bounds: Option<(usize, usize, usize, usize)>,
+    /// The mean position of the live cells.
+    pub centroid: Option<(f32, f32)>,
+    /// The Shannon entropy of the 2x2 blocks the board is made of, from 0
+    /// for a uniform board to 1 when all 16 arrangements are equally
+    /// common.
+    pub entropy: f32,
+    /// The share of cells that were born or died.
+    pub activity: f32,
+}
+
+impl Stats {
+    /// Measures the automaton's current generation against `previous`, the
+    /// cells of the one measured before. A `previous` of a different size
+    /// counts as empty.
+    pub fn measure(previous: &[f32], automaton: &dyn Automaton) -> Self {
+        let (width, height) = (automaton.width(), automaton.height());
+        let cells = automaton.cells();
+        let alive = |i: usize| cells[i] >= 0.5;
+        let was_alive = |i: usize| previous.len() == cells.len() && previous[i] >= 0.5;
+
+        let mut stats = Stats {
+            width,
+            height,
+            ..Default::default()
+        };
+        let (mut sum_x, mut sum_y) = (0.0, 0.0);
+        for y in 0..height {
+            for x in 0..width {
+                let i = y * width + x;
+                match (was_alive(i), alive(i)) {
+                    (false, true) => stats.births += 1,
+                    (true, false) => stats.deaths += 1,
+                    _ => {}
+                }
+                if !alive(i) {
+                    continue;
+                }
+
+                stats.population += 1;
+                sum_x += x as f32;
+                sum_y += y as f32;
+                stats.bounds = Some(match stats.bounds {
+                    None => (x, y, x, y),
+                    Some((left, top, right, bottom)) => {
+                        (left.min(x), top.min(y), right.max(x), bottom.max(y))
+                    }
+                });
+            }
+        }
+
+        if stats.population > 0 {
+            let population = stats.population as f32;
+            stats.centroid = Some((sum_x / population, sum_y / population));
+        }
+        if !cells.is_empty() {
+            stats.activity = (stats.births + stats.deaths) as f32 / cells.len() as f32;
+        }
+
+        // blocks overlap and wrap, so every cell starts one
+        let mut counts = [0usize; 16];
+        for y in 0..height {
+            for x in 0..width {
+                let (right, below) = ((x + 1) % width, (y + 1) % height);
+                let block = alive(y * width + x) as usize
+                    | (alive(y * width + right) as usize) << 1
+                    | (alive(below * width + x) as usize) << 2
+                    | (alive(below * width + right) as usize) << 3;
+                counts[block] += 1;
+            }
+        }
+        let blocks = cells.len() as f32;
+        stats.entropy = counts
+            .iter()
+            .filter(|count| **count > 0)
+            .map(|count| {
+                let p = *count as f32 / blocks;
+                -p * p.log2()
+            })
+            .sum::<f32>()
+            / 4.0;
+
+        stats
+    }
+}
+
+const WORDS: usize = 9;
+
+/// The latest `Stats`, written by one thread and read by any number of
+/// others without locking.
+///
+/// A sequence number around each write lets readers spot one in progress
+/// and read again, so they never see half of one generation and half of
+/// another. Only one thread may store at a time, and bounds are only kept
+/// for boards up to 65536 cells across.
+#[derive(Debug, Default)]
+pub struct Published {
+    sequence: AtomicU64,
+    words: [AtomicU64; WORDS],
+}
+
+impl Published {
+    pub fn store(&self, stats: &Stats) {
+        let sequence = self.sequence.load(Ordering::Relaxed);
+        self.sequence
+            .store(sequence.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        for (word, value) in self.words.iter().zip(pack(stats)) {
+            word.store(value, Ordering::Relaxed);
+        }
+
+        self.sequence
+            .store(sequence.wrapping_add(2), Ordering::Release);
+    }
+
+    /// Waits out any write in progress, so it may spin while the writer
+    /// is descheduled. Real-time threads use `try_load` instead.
+    pub fn load(&self) -> Stats {
+        loop {
+            match self.try_load() {
+                Some(stats) => return stats,
+                None => hint::spin_loop(),
+            }
+        }
+    }
+
+    /// Reads once, giving up if a write is in progress.
+    pub fn try_load(&self) -> Option<Stats> {
+        let before = self.sequence.load(Ordering::Acquire);
+        if before % 2 == 1 {
+            return None;
+        }
+
+        let words = self
+            .words
+            .each_ref()
+            .map(|word| word.load(Ordering::Relaxed));
+        fence(Ordering::Acquire);
+        match self.sequence.load(Ordering::Relaxed) == before {
+            true => Some(unpack(words)),
+            false => None,
+        }
+    }
+}
+
+fn pack(stats: &Stats) -> [u64; WORDS] {
+    let bounds = match stats.bounds {
+        Some((left, top, right, bottom)) => {
+            left as u64 & 0xffff
+                | (top as u64 & 0xffff) << 16
+                | (right as u64 & 0xffff) << 32
+                | (bottom as u64 & 0xffff) << 48
+        }
+        None => u64::MAX,
+    };
+    let centroid = match stats.centroid {
+        Some((x, y)) => x.to_bits() as u64 | (y.to_bits() as u64) << 32,
+        None => u64::MAX,
+    };
+    [
+        stats.width as u64,
+        stats.height as u64,
+        stats.population as u64,
+        stats.births as u64,
+        stats.deaths as u64,
+        bounds,
+        centroid,
+        stats.entropy.to_bits() as u64,
+        stats.activity.to_bits() as u64,
+    ]
+}
+
+fn unpack(words: [u64; WORDS]) -> Stats {
+    let [width, height, population, births, deaths, bounds, centroid, entropy, activity] = words;
+    let field = |word: u64, shift: u32| (word >> shift & 0xffff) as usize;
+    Stats {
+        width: width as usize,
+        height: height as usize,
+        population: population as usize,
+        births: births as usize,
+        deaths: deaths as usize,
+        bounds: (bounds != u64::MAX).then(|| {
+            (
+                field(bounds, 0),
+                field(bounds, 16),
+                field(bounds, 32),
+                field(bounds, 48),
+            )
+        }),
+        centroid: (centroid != u64::MAX).then(|| {
+            (
+                f32::from_bits(centroid as u32),
+                f32::from_bits((centroid >> 32) as u32),
+            )
+        }),
+        entropy: f32::from_bits(entropy as u32),
+        activity: f32::from_bits(activity as u32),
+    }
+}
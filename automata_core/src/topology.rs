@@ -0,0 +1,269 @@
+use std::{fmt, str::FromStr};
+
+/// How the edges of a square board are glued together.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Topology {
+    /// Opposite edges are joined straight across.
+    #[default]
+    Torus,
+    /// Left and right edges are joined straight across, top and bottom are
+    /// joined with a horizontal flip.
+    KleinBottle,
+    /// Both pairs of opposite edges are joined with a flip.
+    ProjectivePlane,
+    /// Everything off the board is permanently dead.
+    Dead,
+    /// Edges reflect back onto the board, so the cell just past an edge is
+    /// the edge cell itself.
+    Mirror,
+}
+
+impl Topology {
+    pub const ALL: [Topology; 5] = [
+        Topology::Torus,
+        Topology::KleinBottle,
+        Topology::ProjectivePlane,
+        Topology::Dead,
+        Topology::Mirror,
+    ];
+
+    /// Maps a possibly out of range coordinate back onto a `size` x `size`
+    /// board, or `None` if it falls off a dead edge.
+    ///
+    /// The x axis is resolved before the y axis, which only matters for the
+    /// corners of the projective plane.
+    pub fn map(&self, x: i32, y: i32, size: usize) -> Option<(usize, usize)> {
+        let n = size as i32;
+        let flip = |p: i32| n - 1 - p;
+
+        let (x, y) = match self {
+            Topology::Torus => (x.rem_euclid(n), y.rem_euclid(n)),
+            Topology::KleinBottle => {
+                let x = x.rem_euclid(n);
+                match y.div_euclid(n) % 2 {
+                    0 => (x, y.rem_euclid(n)),
+                    _ => (flip(x), y.rem_euclid(n)),
+                }
+            }
+            Topology::ProjectivePlane => {
+                let (x, y) = match x.div_euclid(n) % 2 {
+                    0 => (x.rem_euclid(n), y),
+                    _ => (x.rem_euclid(n), flip(y)),
+                };
+                match y.div_euclid(n) % 2 {
+                    0 => (x, y.rem_euclid(n)),
+                    _ => (flip(x), y.rem_euclid(n)),
+                }
+            }
+            Topology::Dead => match (0..n).contains(&x) && (0..n).contains(&y) {
+                true => (x, y),
+                false => return None,
+            },
+            Topology::Mirror => {
+                let reflect = |p: i32| match p.rem_euclid(2 * n) {
+                    p if p >= n => 2 * n - 1 - p,
+                    p => p,
+                };
+                (reflect(x), reflect(y))
+            }
+        };
+
+        Some((x as usize, y as usize))
+    }
+
+    /// The Moore neighborhood of `(x, y)`, one entry per neighbor position.
+    /// The same cell can appear more than once on small or twisted boards.
+    pub fn neighbors(&self, x: usize, y: usize, size: usize) -> [Option<(usize, usize)>; 8] {
+        let mut neighbors = [None; 8];
+        let mut i = 0;
+        for dx in -1..2 {
+            for dy in -1..2 {
+                if dx != 0 || dy != 0 {
+                    neighbors[i] = self.map(x as i32 + dx, y as i32 + dy, size);
+                    i += 1;
+                }
+            }
+        }
+
+        neighbors
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Topology::Torus => "torus",
+            Topology::KleinBottle => "klein",
+            Topology::ProjectivePlane => "projective",
+            Topology::Dead => "dead",
+            Topology::Mirror => "mirror",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Topology {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Topology::ALL
+            .into_iter()
+            .find(|t| t.to_string().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("unknown topology '{s}'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board::{BitBoard, Board},
+        rule::Rule,
+    };
+
+    /// Where a cell at most one step off a `size` board is glued back on,
+    /// written out edge by edge rather than with `Topology::map`.
+    fn glue(topology: Topology, x: i32, y: i32, size: usize) -> Option<(usize, usize)> {
+        let n = size as i32;
+        let wrap = |p: i32| match p {
+            -1 => n - 1,
+            p if p == n => 0,
+            p => p,
+        };
+        let off = |p: i32| p == -1 || p == n;
+
+        let (x, y) = match topology {
+            Topology::Torus => (wrap(x), wrap(y)),
+            Topology::KleinBottle => match off(y) {
+                true => (n - 1 - wrap(x), wrap(y)),
+                false => (wrap(x), y),
+            },
+            Topology::ProjectivePlane => {
+                // across the left or right edge, then the top or bottom
+                let (x, y) = match off(x) {
+                    true => (wrap(x), n - 1 - y),
+                    false => (x, y),
+                };
+                match off(y) {
+                    true => (n - 1 - x, wrap(y)),
+                    false => (x, y),
+                }
+            }
+            Topology::Dead if off(x) || off(y) => return None,
+            Topology::Dead => (x, y),
+            Topology::Mirror => (x.clamp(0, n - 1), y.clamp(0, n - 1)),
+        };
+        Some((x as usize, y as usize))
+    }
+
+    /// A deliberately naive dense step, used as the source of truth for the
+    /// neighbor semantics of each topology. `cells` is indexed `x * size + y`.
+    fn reference_step(cells: &[bool], size: usize, topology: Topology, rule: &Rule) -> Vec<bool> {
+        let mut out = vec![false; size * size];
+        for x in 0..size {
+            for y in 0..size {
+                let mut living = 0;
+                for dx in -1..2 {
+                    for dy in -1..2 {
+                        let neighbor = glue(topology, x as i32 + dx, y as i32 + dy, size);
+                        if let Some((nx, ny)) = neighbor.filter(|_| dx != 0 || dy != 0) {
+                            living += cells[nx * size + ny] as u8;
+                        }
+                    }
+                }
+
+                out[x * size + y] = match cells[x * size + y] {
+                    true => rule.survives(living),
+                    false => rule.born(living),
+                };
+            }
+        }
+
+        out
+    }
+
+    /// Exhaustively checks that `BitBoard` agrees with `reference_step` on a
+    /// board of the given size.
+    ///
+    /// Neighbor counts are linear in the live cells, so it is enough to check
+    /// every single-cell board against every `Bn/Sn` rule: together those pin
+    /// down the exact count each cell sees from each other cell. Returns the
+    /// first live cell for which the two disagree.
+    fn verify(size: usize, topology: Topology) -> Result<(), (usize, usize)> {
+        let mut board = BitBoard::new(size);
+        board.set_topology(topology);
+        let mut cells = vec![false; size * size];
+
+        for x in 0..size {
+            for y in 0..size {
+                for n in 0..9 {
+                    let rule = Rule::from_counts(&[n], &[n]);
+
+                    board.clear();
+                    board.set(x, y, true);
+                    board.step(&rule);
+
+                    cells.fill(false);
+                    cells[x * size + y] = true;
+                    let expected = reference_step(&cells, size, topology, &rule);
+
+                    for i in 0..size {
+                        for j in 0..size {
+                            if board.get(i, j) != expected[i * size + j] {
+                                return Err((x, y));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn glues_edges_by_hand() {
+        // cells just off a 4x4 board, worked out on paper
+        for (topology, (x, y), expected) in [
+            (Topology::Torus, (-1, -1), Some((3, 3))),
+            (Topology::Torus, (4, 1), Some((0, 1))),
+            (Topology::Torus, (2, 4), Some((2, 0))),
+            (Topology::KleinBottle, (-1, -1), Some((0, 3))),
+            (Topology::KleinBottle, (-1, 1), Some((3, 1))),
+            (Topology::KleinBottle, (4, 2), Some((0, 2))),
+            (Topology::KleinBottle, (1, -1), Some((2, 3))),
+            (Topology::KleinBottle, (0, 4), Some((3, 0))),
+            (Topology::KleinBottle, (4, 4), Some((3, 0))),
+            (Topology::ProjectivePlane, (-1, -1), Some((0, 0))),
+            (Topology::ProjectivePlane, (-1, 1), Some((3, 2))),
+            (Topology::ProjectivePlane, (4, 0), Some((0, 3))),
+            (Topology::ProjectivePlane, (1, -1), Some((2, 3))),
+            (Topology::ProjectivePlane, (3, 4), Some((0, 0))),
+            (Topology::ProjectivePlane, (4, 4), Some((3, 3))),
+            (Topology::Dead, (-1, 0), None),
+            (Topology::Dead, (2, 4), None),
+            (Topology::Dead, (3, 3), Some((3, 3))),
+            (Topology::Mirror, (-1, -1), Some((0, 0))),
+            (Topology::Mirror, (4, 2), Some((3, 2))),
+            (Topology::Mirror, (1, 4), Some((1, 3))),
+        ] {
+            assert_eq!(topology.map(x, y, 4), expected, "{topology} at ({x}, {y})");
+            assert_eq!(
+                glue(topology, x, y, 4),
+                expected,
+                "{topology} at ({x}, {y})"
+            );
+        }
+    }
+
+    #[test]
+    fn bit_board_matches_reference() {
+        for size in [1, 2, 3, 4, 5, 8, 13] {
+            for topology in Topology::ALL {
+                if let Err((x, y)) = verify(size, topology) {
+                    panic!("{topology} at {size} cells mismatches for a cell at ({x}, {y})");
+                }
+            }
+        }
+    }
+}
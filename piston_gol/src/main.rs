@@ -3,23 +3,21 @@ extern crate graphics;
 extern crate opengl_graphics;
 extern crate piston;
 
-use std::collections::HashSet;
-
+use automata_core::{
+    library, life::Life, pattern::Rotation, rule::Rule, topology::Topology, Automaton,
+};
 use glutin_window::GlutinWindow as Window;
-use graphics::color::{BLACK, WHITE};
+use graphics::color::BLACK;
 use opengl_graphics::{GlGraphics, OpenGL};
 use piston::event_loop::{EventSettings, Events};
 use piston::input::{RenderArgs, RenderEvent, UpdateArgs, UpdateEvent};
 use piston::window::WindowSettings;
-use rand::rngs::SmallRng;
-use rand::Rng;
-use rand::SeedableRng;
+
+const SIZE: usize = 64;
 
 pub struct App {
     gl: GlGraphics, // OpenGL drawing backend.
-    current_board: HashSet<(i32, i32)>,
-    born_vec: Vec<(i32, i32)>,
-    dying_vec: Vec<(i32, i32)>,
+    life: Life,
 }
 
 impl App {
@@ -27,34 +25,65 @@ impl App {
         use graphics::*;
 
         let square = rectangle::square(0.0, 0.0, 10.0);
+        let life = &self.life;
 
         self.gl.draw(args.viewport(), |c, gl| {
             clear(BLACK, gl);
 
-            for cell in &self.current_board {
-                let (x, y) = (
-                    (args.window_size[0] / 4 as f64) + cell.0 as f64 * 12.0,
-                    (args.window_size[1] / 4 as f64) + cell.1 as f64 * 12.0,
-                );
+            for i in 0..life.width() {
+                for j in 0..life.height() {
+                    let weight = life.cell(i, j);
+                    if weight == 0.0 {
+                        continue;
+                    }
 
-                let transform = c.transform.trans(x, y);
+                    let (x, y) = (
+                        (args.window_size[0] / 4 as f64) + i as f64 * 12.0,
+                        (args.window_size[1] / 4 as f64) + j as f64 * 12.0,
+                    );
 
-                // Draw a box rotating around the middle of the screen.
-                rectangle(WHITE, square, transform, gl);
+                    let transform = c.transform.trans(x, y);
+
+                    // Draw a box rotating around the middle of the screen.
+                    // Refractory cells fade out towards black.
+                    let color = [weight, weight, weight, 1.0];
+                    rectangle(color, square, transform, gl);
+                }
             }
         });
     }
 
     fn update(&mut self, _args: &UpdateArgs) {
-        step(
-            &mut self.current_board,
-            &mut self.born_vec,
-            &mut self.dying_vec,
-        );
+        self.life.step();
     }
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // The first argument, if any, is a B/S or S/B rulestring, e.g. `B36/S23`,
+    // and the second is the topology, e.g. `klein`.
+    let rule = match args.first() {
+        Some(s) => match Rule::parse(s) {
+            Ok(rule) => rule,
+            Err(e) => {
+                eprintln!("invalid rule {s:?}: {e}");
+                return;
+            }
+        },
+        None => Rule::default(),
+    };
+    let topology = match args.get(1) {
+        Some(s) => match s.parse() {
+            Ok(topology) => topology,
+            Err(e) => {
+                eprintln!("{e}");
+                return;
+            }
+        },
+        None => Topology::default(),
+    };
+
     // Change this to OpenGL::V2_1 if not working.
     let opengl = OpenGL::V3_2;
 
@@ -65,33 +94,18 @@ fn main() {
         .build()
         .unwrap();
 
-    let mut current_board = HashSet::with_capacity(64 * 64);
-    let mut rng = SmallRng::seed_from_u64(69);
-
-    // build_random(&mut current_board, &mut rng);
-    const EXPLODE: [(i32, i32); 10] = [
-        (30, 30),
-        (30, 31),
-        (30, 32),
-        (30, 33),
-        (30, 34),
-        (30, 35),
-        (29, 32),
-        (29, 33),
-        (28, 31),
-        (28, 34),
-    ];
-
-    for cell in EXPLODE {
-        current_board.insert(cell);
-    }
+    let mut life = Life::new(SIZE);
+    life.set_rule(rule);
+    life.set_topology(topology);
+
+    // life.seed(69);
+    let explode = library::find("explode").unwrap().pattern();
+    life.stamp(explode.placed(28, 30, Rotation::None, false));
 
     // Create a new game and run it.
     let mut app = App {
         gl: GlGraphics::new(opengl),
-        current_board,
-        born_vec: Vec::with_capacity(64 * 64),
-        dying_vec: Vec::with_capacity(64 * 64),
+        life,
     };
 
     let mut events = Events::new(EventSettings::new());
@@ -105,80 +119,3 @@ fn main() {
         }
     }
 }
-
-pub fn build_random(board: &mut HashSet<(i32, i32)>, rng: &mut SmallRng) {
-    board.clear();
-
-    for i in 0..64 {
-        for j in 0..64 {
-            if rng.gen() {
-                board.insert((i as i32, j as i32));
-            }
-        }
-    }
-}
-pub fn step(
-    current_board: &mut HashSet<(i32, i32)>,
-    born: &mut Vec<(i32, i32)>,
-    dying: &mut Vec<(i32, i32)>,
-) {
-    for cell in current_board.iter() {
-        let neighbors = find_neighbors(&cell);
-
-        let mut living_neighbors: u8 = 0;
-        for neighbor in neighbors.iter() {
-            if current_board.contains(neighbor) {
-                living_neighbors += 1;
-            } else if !born.contains(neighbor) {
-                let neighbors_neighbors = find_neighbors(neighbor);
-                let mut neighbors_living_neighbors: u8 = 0;
-                for neighbor_neighbor in neighbors_neighbors.iter() {
-                    if current_board.contains(neighbor_neighbor) {
-                        neighbors_living_neighbors += 1;
-                    }
-                }
-                if neighbors_living_neighbors == 3 && !born.contains(neighbor) {
-                    born.push(*neighbor);
-                }
-            }
-        }
-        if living_neighbors > 3 || living_neighbors < 2 && !dying.contains(cell) {
-            dying.push(*cell);
-        }
-    }
-    for cell in dying.iter() {
-        current_board.remove(cell);
-    }
-    for cell in born.iter() {
-        current_board.insert(*cell);
-    }
-    dying.clear();
-    born.clear();
-}
-
-pub fn find_neighbors(pos: &(i32, i32)) -> [(i32, i32); 8] {
-    let mut neighbors = [(0, 0); 8];
-    let mut i = 0;
-    for x in -1..2 {
-        for y in -1..2 {
-            if x != 0 || y != 0 {
-                let true_x = match () {
-                    _ if pos.0 + x > 64 => x,
-                    _ if pos.0 + x < 0 => 64 - x,
-                    _ => pos.0 + x,
-                };
-                let true_y = match () {
-                    _ if pos.1 + y > 64 => y,
-                    _ if pos.1 + y < 0 => 64 - y,
-                    _ => pos.1 + y,
-                };
-
-                neighbors[i] = (true_x, true_y);
-                // neighbors[i] = (pos.0 + x, pos.1 + y);
-                i += 1;
-            }
-        }
-    }
-
-    neighbors
-}
@@ -5,6 +5,9 @@ pub const SMOOVE: [f32; FILTER_WINDOW_SIZE] =
     [1 as f32 / FILTER_WINDOW_SIZE as f32; FILTER_WINDOW_SIZE];
 pub const FFT_WINDOW_SIZE: usize = WINDOW_SIZE + FILTER_WINDOW_SIZE - 1;
 
+/// How far kernels are delayed, the middle of the filter.
+pub const KERNEL_DELAY: usize = FILTER_WINDOW_SIZE / 2;
+
 pub const GAIN_COMP: f32 = 1.0 / FFT_WINDOW_SIZE as f32;
 
 pub const SEED: u64 = 69;
@@ -1,40 +1,205 @@
-use crate::{Automata, AutomataParams, Tasks};
-use std::sync::Arc;
+use crate::{modulation, Automata, AutomataParams, RulePreset, Tasks};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use automata_core::{
+    cycle::Status,
+    pattern::Pattern,
+    rule::Rule,
+    stats::{Published, Stats},
+};
 
 use nih_plug::editor::Editor;
-use nih_plug::prelude::AsyncExecutor;
+use nih_plug::prelude::{nih_log, AsyncExecutor};
 use nih_plug_vizia::vizia::prelude::*;
+use nih_plug_vizia::widgets::*;
 use nih_plug_vizia::{assets, create_vizia_editor, ViziaState, ViziaTheming};
 
 #[derive(Lens)]
 struct Data {
     params: Arc<AutomataParams>,
     executor: AsyncExecutor<Automata>,
+    custom_rule: String,
+    custom_mask: String,
+    jump_target: String,
+    pattern_path: String,
+    pattern_status: String,
+    export_path: String,
+    export_generation: String,
+    seed: String,
+    status: Arc<RwLock<Status>>,
+    generation: Arc<AtomicU64>,
+    stats: Arc<Published>,
 }
 
 pub enum GUIEvent {
     PlayPause,
+    StepBack,
+    StepForward,
     Reset,
+    SetCustomRule(String),
+    SetCustomMask(String),
+    JumpTo(String),
+    LoadPattern(String),
+    LoadLibrary,
+    StampLibrary,
+    Export(String),
+    SetExportGeneration(String),
+    Reseed,
+    SetSeed(String),
+    Reroll,
 }
 
 impl Model for Data {
-    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
         event.map(|e, _| match e {
             GUIEvent::PlayPause => self.executor.execute_background(Tasks::Run(1)),
+            GUIEvent::StepBack => self.executor.execute_background(Tasks::StepBack),
+            GUIEvent::StepForward => self.executor.execute_background(Tasks::StepForward),
+            GUIEvent::SetCustomRule(s) => {
+                self.custom_rule = s.clone();
+                *self.params.custom_rule.write().unwrap() = s.clone();
+            }
+            GUIEvent::SetCustomMask(s) => {
+                self.custom_mask = s.clone();
+                *self.params.custom_mask.write().unwrap() = s.clone();
+            }
+            GUIEvent::JumpTo(s) => match parse_generation(s) {
+                Some(generation) => {
+                    self.jump_target = s.clone();
+                    self.executor.execute_background(Tasks::JumpTo(generation));
+                }
+                None => nih_log!("invalid generation {s:?}"),
+            },
+            GUIEvent::LoadPattern(path) => {
+                self.pattern_path = path.clone();
+                let pattern = std::fs::read_to_string(path.trim())
+                    .map_err(|e| e.to_string())
+                    .and_then(|text| Pattern::parse(&text).map_err(|e| e.to_string()));
+
+                match pattern {
+                    Ok(pattern) => self.load_pattern(cx, pattern),
+                    Err(e) => {
+                        nih_log!("couldn't load pattern {path:?}: {e}");
+                        self.pattern_status = e;
+                    }
+                }
+            }
+            GUIEvent::LoadLibrary => {
+                let pattern = self.params.library_entry().pattern();
+                self.load_pattern(cx, pattern);
+            }
+            GUIEvent::StampLibrary => {
+                let pattern = self.params.library_entry().pattern();
+                self.executor
+                    .execute_background(Tasks::StampPattern(pattern));
+            }
+            GUIEvent::Export(path) => {
+                self.export_path = path.clone();
+                // blank exports the current generation
+                let generation = match self.export_generation.trim() {
+                    "" => None,
+                    s => match parse_generation(s) {
+                        Some(generation) => Some(generation),
+                        None => {
+                            nih_log!("invalid generation {s:?}");
+                            return;
+                        }
+                    },
+                };
+                self.executor
+                    .execute_background(Tasks::Export(PathBuf::from(path.trim()), generation));
+            }
+            GUIEvent::SetExportGeneration(s) => self.export_generation = s.clone(),
+            GUIEvent::Reseed => self.executor.execute_background(Tasks::Reseed),
+            GUIEvent::SetSeed(s) => match s.trim().parse() {
+                Ok(seed) => self.set_seed(seed),
+                Err(_) => nih_log!("invalid seed {s:?}"),
+            },
+            GUIEvent::Reroll => self.set_seed(rand::random()),
             _ => {}
         })
     }
 }
 
+impl Data {
+    /// Saves the seed with the session and reseeds every engine from it.
+    fn set_seed(&mut self, seed: u64) {
+        self.seed = seed.to_string();
+        *self.params.seed.write().unwrap() = seed;
+        self.executor.execute_background(Tasks::SetSeed(seed));
+    }
+
+    fn load_pattern(&mut self, cx: &mut EventContext, pattern: Pattern) {
+        self.pattern_status = format!(
+            "loaded {} ({}x{})",
+            pattern.name.as_deref().unwrap_or("pattern"),
+            pattern.width,
+            pattern.height
+        );
+
+        // a rule in the pattern takes over from the current one
+        if let Some(rule) = pattern.rule {
+            let preset = match rule == Rule::CONWAY {
+                true => RulePreset::Conway,
+                false => {
+                    self.custom_rule = rule.to_string();
+                    *self.params.custom_rule.write().unwrap() = rule.to_string();
+                    RulePreset::Custom
+                }
+            };
+
+            let param = &self.params.rule;
+            cx.emit(ParamEvent::BeginSetParameter(param).upcast());
+            cx.emit(ParamEvent::SetParameter(param, preset).upcast());
+            cx.emit(ParamEvent::EndSetParameter(param).upcast());
+        }
+
+        self.executor
+            .execute_background(Tasks::LoadPattern(pattern));
+    }
+}
+
+/// A line summing up a generation's statistics.
+fn describe(stats: &Stats) -> String {
+    let mut line = format!(
+        "population {}, +{} -{}, entropy {:.2}, activity {:.1}%",
+        stats.population,
+        stats.births,
+        stats.deaths,
+        stats.entropy,
+        stats.activity * 100.0
+    );
+    if let (Some((left, top, right, bottom)), Some((x, y))) = (stats.bounds, stats.centroid) {
+        line += &format!(
+            ", {}x{} around ({x:.1}, {y:.1})",
+            right - left + 1,
+            bottom - top + 1
+        );
+    }
+    line
+}
+
+/// A generation typed into the editor, allowing digit grouping, e.g.
+/// 1,000,000 or 1_000_000.
+fn parse_generation(s: &str) -> Option<u64> {
+    let digits: String = s.chars().filter(|c| !matches!(c, ',' | '_')).collect();
+    digits.trim().parse().ok()
+}
+
 // Makes sense to also define this here, makes it a bit easier to keep track of
 pub(crate) fn default_state() -> Arc<ViziaState> {
-    ViziaState::new(|| (400, 300))
+    ViziaState::new(|| (2100, 720))
 }
 
 pub(crate) fn create(
     params: Arc<AutomataParams>,
     editor_state: Arc<ViziaState>,
     executor: AsyncExecutor<Automata>,
+    status: Arc<RwLock<Status>>,
+    generation: Arc<AtomicU64>,
+    stats: Arc<Published>,
 ) -> Option<Box<dyn Editor>> {
     let e = create_vizia_editor(editor_state, ViziaTheming::Custom, move |cx, _| {
         assets::register_noto_sans_light(cx);
@@ -43,6 +208,17 @@ pub(crate) fn create(
         Data {
             params: params.clone(),
             executor: executor.clone(),
+            custom_rule: params.custom_rule.read().unwrap().clone(),
+            custom_mask: params.custom_mask.read().unwrap().clone(),
+            jump_target: String::from("1,000,000"),
+            pattern_path: String::new(),
+            pattern_status: String::from("RLE, .cells or Life 1.06 file"),
+            export_path: String::new(),
+            export_generation: String::new(),
+            seed: params.seed.read().unwrap().to_string(),
+            status: status.clone(),
+            generation: generation.clone(),
+            stats: stats.clone(),
         }
         .build(cx);
 
@@ -53,11 +229,190 @@ pub(crate) fn create(
                 .height(Pixels(50.0))
                 .child_top(Stretch(1.0))
                 .child_bottom(Pixels(0.0));
-            Button::new(
-                cx,
-                |ex| ex.emit(GUIEvent::PlayPause),
-                |cx| Label::new(cx, "step"),
-            );
+            HStack::new(cx, |cx| {
+                Button::new(
+                    cx,
+                    |ex| ex.emit(GUIEvent::StepBack),
+                    |cx| Label::new(cx, "back"),
+                );
+                Button::new(
+                    cx,
+                    |ex| ex.emit(GUIEvent::PlayPause),
+                    |cx| Label::new(cx, "step"),
+                );
+                Button::new(
+                    cx,
+                    |ex| ex.emit(GUIEvent::StepForward),
+                    |cx| Label::new(cx, "forward"),
+                );
+                Label::new(
+                    cx,
+                    Data::generation.map(|generation| {
+                        format!("generation {}", generation.load(Ordering::Relaxed))
+                    }),
+                );
+            })
+            .height(Auto)
+            .col_between(Pixels(10.0));
+            Label::new(cx, Data::stats.map(|stats| describe(&stats.load())));
+            HStack::new(cx, |cx| {
+                ParamSlider::new(cx, Data::params, |params| &params.engine);
+                ParamSlider::new(cx, Data::params, |params| &params.kernel_mapping);
+                ParamSlider::new(cx, Data::params, |params| &params.normalization);
+                ParamSlider::new(cx, Data::params, |params| &params.normalization_fallback);
+                ParamSlider::new(cx, Data::params, |params| &params.kernel_domain);
+                ParamSlider::new(cx, Data::params, |params| &params.spectral_grouping);
+                ParamSlider::new(cx, Data::params, |params| &params.spectral_root);
+                ParamButton::new(cx, Data::params, |params| &params.spectral_phase);
+            })
+            .height(Auto)
+            .col_between(Pixels(20.0));
+            HStack::new(cx, |cx| {
+                Label::new(
+                    cx,
+                    Data::status.map(|status| status.read().unwrap().to_string()),
+                );
+                ParamSlider::new(cx, Data::params, |params| &params.stagnation_remedy);
+                ParamSlider::new(cx, Data::params, |params| &params.stagnation_noise);
+            })
+            .height(Auto)
+            .col_between(Pixels(20.0));
+            HStack::new(cx, |cx| {
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "rule");
+                    ParamSlider::new(cx, Data::params, |params| &params.rule);
+                    Textbox::new(cx, Data::custom_rule)
+                        .on_submit(|cx, text, _| cx.emit(GUIEvent::SetCustomRule(text)))
+                        .width(Pixels(200.0));
+                    Label::new(cx, "topology");
+                    ParamSlider::new(cx, Data::params, |params| &params.topology);
+                    Label::new(cx, "neighborhood");
+                    ParamSlider::new(cx, Data::params, |params| &params.neighborhood);
+                    ParamSlider::new(cx, Data::params, |params| &params.radius);
+                    Textbox::new(cx, Data::custom_mask)
+                        .on_submit(|cx, text, _| cx.emit(GUIEvent::SetCustomMask(text)))
+                        .width(Pixels(200.0));
+                    Label::new(cx, "pattern file");
+                    Textbox::new(cx, Data::pattern_path)
+                        .on_submit(|cx, text, _| cx.emit(GUIEvent::LoadPattern(text)))
+                        .width(Pixels(200.0));
+                    Label::new(cx, Data::pattern_status);
+                    ParamSlider::new(cx, Data::params, |params| &params.pattern_x);
+                    ParamSlider::new(cx, Data::params, |params| &params.pattern_y);
+                    ParamSlider::new(cx, Data::params, |params| &params.pattern_rotation);
+                    ParamButton::new(cx, Data::params, |params| &params.pattern_mirror);
+                    ParamSlider::new(cx, Data::params, |params| &params.library);
+                    HStack::new(cx, |cx| {
+                        Button::new(
+                            cx,
+                            |ex| ex.emit(GUIEvent::LoadLibrary),
+                            |cx| Label::new(cx, "load"),
+                        );
+                        Button::new(
+                            cx,
+                            |ex| ex.emit(GUIEvent::StampLibrary),
+                            |cx| Label::new(cx, "stamp"),
+                        );
+                    })
+                    .height(Auto);
+                    Label::new(cx, "export to .rle, .pbm or .pgm");
+                    Textbox::new(cx, Data::export_path)
+                        .on_submit(|cx, text, _| cx.emit(GUIEvent::Export(text)))
+                        .width(Pixels(200.0));
+                    Label::new(cx, "export generation, blank for the current one");
+                    Textbox::new(cx, Data::export_generation)
+                        .on_submit(|cx, text, _| cx.emit(GUIEvent::SetExportGeneration(text)))
+                        .width(Pixels(200.0));
+                    Label::new(cx, "jump to generation");
+                    Textbox::new(cx, Data::jump_target)
+                        .on_submit(|cx, text, _| cx.emit(GUIEvent::JumpTo(text)))
+                        .width(Pixels(200.0));
+                });
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "seeding");
+                    ParamSlider::new(cx, Data::params, |params| &params.seed_source);
+                    ParamSlider::new(cx, Data::params, |params| &params.seed_density);
+                    ParamSlider::new(cx, Data::params, |params| &params.seed_symmetry);
+                    ParamSlider::new(cx, Data::params, |params| &params.seed_noise);
+                    ParamSlider::new(cx, Data::params, |params| &params.seed_clump_size);
+                    ParamSlider::new(cx, Data::params, |params| &params.seed_soup_size);
+                    Label::new(cx, "seed");
+                    Textbox::new(cx, Data::seed)
+                        .on_submit(|cx, text, _| cx.emit(GUIEvent::SetSeed(text)))
+                        .width(Pixels(200.0));
+                    HStack::new(cx, |cx| {
+                        Button::new(
+                            cx,
+                            |ex| ex.emit(GUIEvent::Reseed),
+                            |cx| Label::new(cx, "reseed"),
+                        );
+                        Button::new(
+                            cx,
+                            |ex| ex.emit(GUIEvent::Reroll),
+                            |cx| Label::new(cx, "reroll"),
+                        );
+                    })
+                    .height(Auto);
+                });
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "reversible");
+                    ParamSlider::new(cx, Data::params, |params| &params.block_rule);
+                    ParamSlider::new(cx, Data::params, |params| &params.palindrome_length);
+                });
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "post-processing");
+                    ParamSlider::new(cx, Data::params, |params| &params.kernel_transition);
+                    ParamSlider::new(cx, Data::params, |params| &params.transition_time);
+                    ParamSlider::new(cx, Data::params, |params| &params.post_window);
+                    ParamSlider::new(cx, Data::params, |params| &params.post_kaiser_beta);
+                    ParamButton::new(cx, Data::params, |params| &params.post_smoothing);
+                    ParamSlider::new(cx, Data::params, |params| &params.post_smoothing_length);
+                    ParamSlider::new(cx, Data::params, |params| &params.post_phase);
+                    ParamButton::new(cx, Data::params, |params| &params.post_remove_dc);
+                });
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "modulation");
+                    for slot in 0..modulation::SLOTS {
+                        HStack::new(cx, |cx| {
+                            ParamSlider::new(cx, Data::params, move |params| {
+                                &params.modulation[slot].source
+                            });
+                            ParamSlider::new(cx, Data::params, move |params| {
+                                &params.modulation[slot].destination
+                            });
+                            ParamSlider::new(cx, Data::params, move |params| {
+                                &params.modulation[slot].amount
+                            });
+                        })
+                        .height(Auto);
+                    }
+                });
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "lenia");
+                    ParamSlider::new(cx, Data::params, |params| &params.lenia_radius);
+                    ParamSlider::new(cx, Data::params, |params| &params.lenia_mu);
+                    ParamSlider::new(cx, Data::params, |params| &params.lenia_sigma);
+                    ParamSlider::new(cx, Data::params, |params| &params.lenia_dt);
+                });
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "gray-scott");
+                    ParamSlider::new(cx, Data::params, |params| &params.gs_regime);
+                    ParamSlider::new(cx, Data::params, |params| &params.gs_feed);
+                    ParamSlider::new(cx, Data::params, |params| &params.gs_kill);
+                    ParamSlider::new(cx, Data::params, |params| &params.gs_diffuse_u);
+                    ParamSlider::new(cx, Data::params, |params| &params.gs_diffuse_v);
+                    ParamSlider::new(cx, Data::params, |params| &params.gs_iterations);
+                });
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "1d");
+                    ParamSlider::new(cx, Data::params, |params| &params.line_rule);
+                    ParamSlider::new(cx, Data::params, |params| &params.line_elementary);
+                    ParamSlider::new(cx, Data::params, |params| &params.line_colors);
+                    ParamSlider::new(cx, Data::params, |params| &params.line_code);
+                    ParamSlider::new(cx, Data::params, |params| &params.line_mapping);
+                });
+            })
+            .col_between(Pixels(20.0));
         })
         .row_between(Pixels(0.0))
         .child_left(Stretch(1.0))
@@ -1,66 +1,415 @@
-use std::{collections::HashSet, sync::Arc};
-
-use nih_plug::prelude::nih_log;
-use rand::{rngs::SmallRng, Rng, SeedableRng};
-use realfft::{num_complex::Complex, RealFftPlanner, RealToComplex};
+use std::{
+    io,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use automata_core::{
+    board,
+    cycle::{Detector, Status},
+    elementary::{Elementary, LineRule},
+    export::{self, Format},
+    gray_scott::GrayScott,
+    hashlife::HashLife,
+    history::History,
+    kernel::{self, KernelMapper},
+    lenia::Lenia,
+    life::Life,
+    neighborhood::Neighborhood,
+    normalize::{Degenerate, Normalization},
+    pattern::{Pattern, Rotation},
+    pool::Pool,
+    post::{Chain, Window},
+    reversible::{BlockRule, Margolus, Reversible, SecondOrder},
+    rule::Rule,
+    seeder::{self, SeedRng, Seeder},
+    spectrum::Spectral,
+    stats::{Published, Stats},
+    topology::Topology,
+    Automaton,
+};
+use nih_plug::prelude::{nih_log, Enum};
+use rand::Rng;
+use realfft::{num_complex::Complex, ComplexToReal, RealFftPlanner, RealToComplex};
 use rtrb::Producer;
 
+/// Which automaton drives the kernel. Each engine keeps its own state, so
+/// switching back and forth picks up where it left off.
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+pub enum Engine {
+    Life,
+    Lenia,
+    #[name = "Gray-Scott"]
+    GrayScott,
+    #[name = "1D"]
+    Elementary,
+    /// Life on an unbounded plane, which can jump far ahead.
+    #[name = "Hashlife"]
+    HashLife,
+    /// The Life rule made reversible by XORing with the generation before.
+    #[name = "Second order"]
+    SecondOrder,
+    /// Reversible 2x2 block rules.
+    Margolus,
+}
+
+/// How the one dimensional engine becomes a kernel.
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+pub enum LineMapping {
+    /// The current row is the kernel, one cell per tap.
+    #[name = "current row"]
+    Row,
+    /// The spacetime diagram is treated like any other board.
+    #[name = "spacetime"]
+    Spacetime,
+}
+
+/// How boards become kernels. Every engine can use any of them, except
+/// that the 1D engine's current row mapping takes over when selected.
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+pub enum KernelMapping {
+    /// Row and column sums, negated on every other tap.
+    #[name = "row + column sums"]
+    Cross,
+    #[name = "row sums"]
+    Rows,
+    #[name = "column sums"]
+    Columns,
+    #[name = "diagonals"]
+    Diagonals,
+    #[name = "Hilbert curve"]
+    Hilbert,
+    /// Rings around the middle of the board, from the middle out.
+    #[name = "radial"]
+    Radial,
+    /// Row and column sums weighted by how long cells have been alive.
+    #[name = "cell age"]
+    Age,
+}
+
+/// What replaces a kernel that can't be normalized, or that comes from an
+/// empty board.
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+pub enum Fallback {
+    #[name = "hold previous kernel"]
+    Previous,
+    /// An impulse, so the audio passes through unfiltered.
+    #[name = "pass through"]
+    Unity,
+}
+
+/// What to do when the current engine dies out or falls into a cycle.
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+pub enum Remedy {
+    #[name = "do nothing"]
+    Nothing,
+    /// Replaces the board using the seed parameters.
+    #[name = "reseed"]
+    Reseed,
+    /// Flips a random scattering of cells across the board.
+    #[name = "inject noise"]
+    Noise,
+    /// Scrambles a random square a quarter of the board across.
+    #[name = "perturb a region"]
+    Perturb,
+}
+
+/// Long enough for a glider to cross the board and come back.
+const MAX_PERIOD: usize = 256;
+
+/// How many past generations can be stepped back through.
+const HISTORY_LENGTH: usize = 1024;
+
+/// The longest sweep that keeps every generation it passes in the history,
+/// generation 0 included.
+pub const MAX_SWEEP: u64 = HISTORY_LENGTH as u64 - 1;
+
+/// The furthest engines other than Hashlife will step ahead for a jump.
+const MAX_JUMP: u64 = 100_000;
+
+/// One of each engine, so that switching between them keeps their state.
+struct Engines {
+    life: Life,
+    lenia: Lenia,
+    gray_scott: GrayScott,
+    elementary: Elementary,
+    hashlife: HashLife,
+    second_order: SecondOrder,
+    margolus: Margolus,
+}
+
+/// One of each mapper, so each keeps its state while another is used.
+/// Cell ages are updated on every emit, whichever mapper is selected.
+#[derive(Default)]
+struct Mappers {
+    cross: kernel::CrossSums,
+    rows: kernel::RowSums,
+    columns: kernel::ColumnSums,
+    diagonals: kernel::Diagonals,
+    hilbert: kernel::Hilbert,
+    radial: kernel::Radial,
+    age: kernel::Age,
+}
+
+impl Mappers {
+    fn get_mut(&mut self, mapping: KernelMapping) -> &mut dyn KernelMapper {
+        match mapping {
+            KernelMapping::Cross => &mut self.cross,
+            KernelMapping::Rows => &mut self.rows,
+            KernelMapping::Columns => &mut self.columns,
+            KernelMapping::Diagonals => &mut self.diagonals,
+            KernelMapping::Hilbert => &mut self.hilbert,
+            KernelMapping::Radial => &mut self.radial,
+            KernelMapping::Age => &mut self.age,
+        }
+    }
+}
+
+impl Engines {
+    fn get(&self, engine: Engine) -> &dyn Automaton {
+        match engine {
+            Engine::Life => &self.life,
+            Engine::Lenia => &self.lenia,
+            Engine::GrayScott => &self.gray_scott,
+            Engine::Elementary => &self.elementary,
+            Engine::HashLife => &self.hashlife,
+            Engine::SecondOrder => &self.second_order,
+            Engine::Margolus => &self.margolus,
+        }
+    }
+
+    fn get_mut(&mut self, engine: Engine) -> &mut dyn Automaton {
+        match engine {
+            Engine::Life => &mut self.life,
+            Engine::Lenia => &mut self.lenia,
+            Engine::GrayScott => &mut self.gray_scott,
+            Engine::Elementary => &mut self.elementary,
+            Engine::HashLife => &mut self.hashlife,
+            Engine::SecondOrder => &mut self.second_order,
+            Engine::Margolus => &mut self.margolus,
+        }
+    }
+
+    /// The engine, if it can be stepped backwards without the history.
+    fn reversible(&mut self, engine: Engine) -> Option<&mut dyn Reversible> {
+        match engine {
+            Engine::SecondOrder => Some(&mut self.second_order),
+            Engine::Margolus => Some(&mut self.margolus),
+            _ => None,
+        }
+    }
+}
+
 pub struct GOL {
-    current_board: HashSet<(i32, i32)>,
+    engine: Engine,
+    engines: Engines,
+    line_mapping: LineMapping,
+    mapping: KernelMapping,
+    mappers: Mappers,
+    /// Builds kernels in the frequency domain instead, when set.
+    spectral: Option<Spectral>,
+    sample_rate: f32,
+    post: Chain,
+    normalization: Normalization,
+    fallback: Fallback,
+    /// The last kernel that normalized cleanly, for `Fallback::Previous`.
+    last_taps: Vec<f32>,
+    /// Whether the last kernel was replaced by the fallback.
+    degenerate: bool,
     prod: Producer<Complex<f32>>,
-    rng: SmallRng,
     fft: Arc<dyn RealToComplex<f32>>,
+    ifft: Arc<dyn ComplexToReal<f32>>,
     real_buff: Vec<f32>,
     comp_buff: Vec<Complex<f32>>,
     size: usize,
+    seed: u64,
+    seeder: Seeder,
+    /// The seeder every engine was last seeded with.
+    seeded: Seeder,
+    detector: Detector,
+    status: Arc<RwLock<Status>>,
+    remedy: Remedy,
+    noise: f32,
+    rng: SeedRng,
+    history: History,
+    generation: Arc<AtomicU64>,
+    sweep: u64,
+    backwards: bool,
+    stats: Arc<Published>,
+    /// The cells behind the last kernel, to count births and deaths from.
+    previous: Vec<f32>,
 }
 
 impl GOL {
-    pub fn new(prod: Producer<Complex<f32>>, size: usize, fft_size: usize, seed: u64) -> Self {
+    pub fn new(
+        prod: Producer<Complex<f32>>,
+        size: usize,
+        fft_size: usize,
+        seed: u64,
+        status: Arc<RwLock<Status>>,
+        generation: Arc<AtomicU64>,
+        stats: Arc<Published>,
+    ) -> Self {
         let mut planner = RealFftPlanner::new();
         let fft = planner.plan_fft_forward(fft_size);
+        let ifft = planner.plan_fft_inverse(fft_size);
         let real_buff = fft.make_input_vec();
         let comp_buff = fft.make_output_vec();
 
-        let rng = SmallRng::seed_from_u64(seed);
-
         let mut gol = Self {
-            current_board: HashSet::with_capacity(size * size),
+            engine: Engine::Life,
+            engines: Engines {
+                life: Life::new(size),
+                lenia: Lenia::new(size),
+                gray_scott: GrayScott::new(size),
+                elementary: Elementary::new(size, size),
+                hashlife: HashLife::new(size),
+                second_order: SecondOrder::new(size),
+                margolus: Margolus::new(size),
+            },
+            line_mapping: LineMapping::Row,
+            mapping: KernelMapping::Cross,
+            mappers: Mappers::default(),
+            spectral: None,
+            sample_rate: 44100.0,
+            post: Chain::default(),
+            normalization: Normalization::DcGain,
+            fallback: Fallback::Previous,
+            last_taps: vec![],
+            degenerate: false,
             prod,
             size,
-            rng,
             fft,
+            ifft,
             real_buff,
             comp_buff,
+            seed,
+            seeder: Seeder::default(),
+            seeded: Seeder::default(),
+            detector: Detector::new(MAX_PERIOD),
+            status,
+            remedy: Remedy::Nothing,
+            noise: 0.0,
+            rng: remedy_rng(seed),
+            history: History::new(HISTORY_LENGTH),
+            generation,
+            sweep: 0,
+            backwards: false,
+            stats,
+            previous: vec![],
         };
 
-        gol.build_random();
+        // a board as wide as the plugin's kernel is too small to be worth
+        // splitting, so the pool only starts for larger boards
+        if board::splits(size) {
+            let pool = Arc::new(Pool::for_cores());
+            gol.engines.life.set_pool(Some(pool.clone()));
+            gol.engines.second_order.set_pool(Some(pool));
+        }
 
-        gol.build_ir();
+        gol.seed_engines();
+        gol.restart();
+        gol.build_kernel();
+        gol.measure();
+        gol.write_kernel();
 
-        match gol
-            .fft
-            .process_with_scratch(&mut gol.real_buff, &mut gol.comp_buff, &mut [])
-        {
-            Ok(_) => {}
-            Err(_) => nih_log!("error with game fft"),
+        gol
+    }
+
+    pub fn set_engine(&mut self, engine: Engine) {
+        if engine != self.engine {
+            self.engine = engine;
+            self.restart();
         }
+    }
 
-        match gol.prod.write_chunk(gol.comp_buff.len()) {
-            Ok(mut p) => {
-                let (s1, s2) = p.as_mut_slices();
-                let len1 = s1.len();
-                let len2 = s2.len();
+    pub fn set_lenia(&mut self, radius: f32, mu: f32, sigma: f32, dt: f32) {
+        self.engines.lenia.set_radius(radius);
+        self.engines.lenia.set_growth(mu, sigma);
+        self.engines.lenia.set_dt(dt);
+    }
 
-                s1.copy_from_slice(&gol.comp_buff[0..len1]);
-                s2.copy_from_slice(&gol.comp_buff[len1..len1 + len2]);
+    pub fn set_gray_scott(&mut self, feed: f32, kill: f32, diffuse_u: f32, diffuse_v: f32) {
+        self.engines.gray_scott.set_rates(feed, kill, diffuse_u, diffuse_v);
+    }
 
-                p.commit_all();
-            }
-            Err(_) => nih_log!("error writing chunk"),
-        }
+    pub fn set_gray_scott_iterations(&mut self, iterations: usize) {
+        self.engines.gray_scott.set_iterations(iterations);
+    }
 
-        gol
+    pub fn set_line_rule(&mut self, rule: LineRule, mapping: LineMapping) {
+        self.engines.elementary.set_rule(rule);
+        self.line_mapping = mapping;
+    }
+
+    pub fn set_mapping(&mut self, mapping: KernelMapping) {
+        self.mapping = mapping;
+    }
+
+    /// The stages run on kernels around normalizing them.
+    pub fn set_post(&mut self, post: Chain) {
+        self.post = post;
+    }
+
+    pub fn set_normalization(&mut self, normalization: Normalization, fallback: Fallback) {
+        self.normalization = normalization;
+        self.fallback = fallback;
+    }
+
+    pub fn set_spectral(&mut self, spectral: Option<Spectral>) {
+        self.spectral = spectral;
+    }
+
+    /// Only needed to place the bands of spectral kernels.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.engines.life.set_rule(rule);
+        self.engines.hashlife.set_rule(rule);
+        self.engines.second_order.set_rule(rule);
+    }
+
+    pub fn set_block_rule(&mut self, rule: BlockRule) {
+        self.engines.margolus.set_rule(rule);
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.engines.life.set_topology(topology);
+        self.engines.lenia.set_topology(topology);
+        self.engines.gray_scott.set_topology(topology);
+        self.engines.elementary.set_topology(topology);
+        self.engines.second_order.set_topology(topology);
+    }
+
+    pub fn set_neighborhood(&mut self, neighborhood: Neighborhood) {
+        self.engines.life.set_neighborhood(neighborhood.clone());
+        self.engines.second_order.set_neighborhood(neighborhood);
+    }
+
+    pub fn set_seeder(&mut self, seeder: Seeder) {
+        self.seeder = seeder;
+    }
+
+    /// `noise` is the share of cells flipped by `Remedy::Noise`.
+    pub fn set_remedy(&mut self, remedy: Remedy, noise: f32) {
+        self.remedy = remedy;
+        self.noise = noise;
+    }
+
+    /// Runs back and forth between generation 0 and `length`, so the
+    /// kernel retraces itself. Reversible engines can sweep any distance,
+    /// the rest only as far back as the history goes, `MAX_SWEEP`. 0 runs
+    /// forwards as usual.
+    pub fn set_sweep(&mut self, length: u64) {
+        self.sweep = length;
+        if length == 0 {
+            self.backwards = false;
+        }
     }
 
     pub fn start(&mut self, len: usize) {
@@ -69,18 +418,300 @@ impl GOL {
         }
     }
 
+    /// The generation the current engine is on, counted from the last time
+    /// its board was replaced.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    /// Goes to `generation` and emits the kernel for it. Generations still
+    /// in the history are brought back from it. Later ones are stepped to,
+    /// except on Hashlife which gets there directly but forgets the history
+    /// on the way.
+    pub fn jump_to(&mut self, generation: u64) {
+        if let Some(state) = self.history.get(generation) {
+            self.engines.get_mut(self.engine).restore(&state);
+            self.generation.store(generation, Ordering::Relaxed);
+            self.detector.clear();
+            self.emit();
+            return;
+        }
+
+        let current = self.generation();
+        match generation.checked_sub(current) {
+            Some(generations) if self.engine == Engine::HashLife => {
+                self.engines.hashlife.advance(generations);
+                self.generation.store(generation, Ordering::Relaxed);
+                self.detector.clear();
+                self.record();
+            }
+            Some(generations) if generations <= MAX_JUMP => {
+                for _ in 0..generations {
+                    self.step();
+                }
+            }
+            Some(_) => {
+                nih_log!(
+                    "generation {generation} is too far ahead, only hashlife can jump that far"
+                );
+                return;
+            }
+            None => {
+                nih_log!("generation {generation} is no longer in the history");
+                return;
+            }
+        }
+
+        self.emit();
+    }
+
+    /// Goes back a generation. Reversible engines work it out from the
+    /// current one, the rest need it to still be in the history.
+    pub fn step_back(&mut self) {
+        let Some(generation) = self.generation().checked_sub(1) else {
+            nih_log!("already at the first generation");
+            return;
+        };
+
+        match self.engines.reversible(self.engine) {
+            Some(automaton) => {
+                automaton.step_back();
+                self.generation.store(generation, Ordering::Relaxed);
+                self.detector.clear();
+                self.record();
+                self.emit();
+            }
+            None => self.jump_to(generation),
+        }
+    }
+
+    /// Goes forward a generation, from the history if it is there so that
+    /// stepping back and forth retraces the same generations.
+    pub fn step_forward(&mut self) {
+        self.jump_to(self.generation() + 1);
+    }
+
+    /// Adds a pattern to the Life and Hashlife engines, on top of what is
+    /// already there, with its middle at `centre`, and emits the kernel.
+    pub fn stamp(
+        &mut self,
+        pattern: &Pattern,
+        centre: (i64, i64),
+        rotation: Rotation,
+        mirror: bool,
+    ) {
+        let (width, height) = pattern.rotated_size(rotation);
+        let x = centre.0 - width as i64 / 2;
+        let y = centre.1 - height as i64 / 2;
+
+        self.engines
+            .life
+            .stamp(pattern.placed(x, y, rotation, mirror));
+        self.engines
+            .hashlife
+            .stamp(pattern.placed(x, y, rotation, mirror));
+
+        // the stamped board takes the current generation's place
+        self.detector.clear();
+        self.record();
+        self.emit();
+    }
+
+    /// Like `stamp`, but clears the Life and Hashlife engines first, and
+    /// counts generations from the loaded pattern.
+    pub fn load_pattern(
+        &mut self,
+        pattern: &Pattern,
+        centre: (i64, i64),
+        rotation: Rotation,
+        mirror: bool,
+    ) {
+        self.engines.life.reset();
+        self.engines.hashlife.reset();
+        self.stamp(pattern, centre, rotation, mirror);
+        if matches!(self.engine, Engine::Life | Engine::HashLife) {
+            self.restart();
+        }
+    }
+
+    /// Restarts every engine from the seed parameters when they or the seed
+    /// have changed since the engines were seeded, so a saved session brings
+    /// back the same evolution.
+    pub fn set_seed(&mut self, seed: u64) {
+        if seed != self.seed || self.seeder != self.seeded {
+            self.seed = seed;
+            self.rng = remedy_rng(seed);
+            self.seed_engines();
+            self.restart();
+            self.emit();
+        }
+    }
+
+    /// Replaces the current engine's board with a fresh seed, and emits the
+    /// kernel.
+    pub fn reseed(&mut self) {
+        let cells = self.seeder.cells(self.size, &mut seeder::rng(self.seed));
+        self.engines.get_mut(self.engine).load(&cells);
+        self.restart();
+        self.emit();
+    }
+
+    fn seed_engines(&mut self) {
+        let cells = self.seeder.cells(self.size, &mut seeder::rng(self.seed));
+        for engine in [
+            Engine::Life,
+            Engine::Lenia,
+            Engine::GrayScott,
+            Engine::Elementary,
+            Engine::HashLife,
+            Engine::SecondOrder,
+            Engine::Margolus,
+        ] {
+            self.engines.get_mut(engine).load(&cells);
+        }
+        self.seeded = self.seeder.clone();
+    }
+
+    /// The current generation's live cells, with the rule for the Life
+    /// engines. Other engines count cells at least half way to full.
+    pub fn snapshot(&self) -> Pattern {
+        match self.engine {
+            Engine::Life => self.engines.life.pattern(),
+            Engine::HashLife => self.engines.hashlife.pattern(),
+            engine => {
+                let automaton = self.engines.get(engine);
+                let mut cells = vec![];
+                for y in 0..automaton.height() {
+                    for x in 0..automaton.width() {
+                        if automaton.cell(x, y) >= 0.5 {
+                            cells.push((x as i64, y as i64));
+                        }
+                    }
+                }
+                Pattern::from_cells(None, None, &cells)
+            }
+        }
+    }
+
+    /// Saves `generation`, or the current one if `None`, in the format
+    /// given by the extension. Earlier generations are brought back from the
+    /// history for the write, and the board put back afterwards.
+    pub fn export(&mut self, path: &Path, generation: Option<u64>) -> io::Result<()> {
+        let generation = match generation {
+            Some(generation) if generation != self.generation() => generation,
+            _ => return self.write(path),
+        };
+        let Some(state) = self.history.get(generation) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("generation {generation} isn't in the history"),
+            ));
+        };
+
+        let automaton = self.engines.get_mut(self.engine);
+        let current = automaton.save();
+        automaton.restore(&state);
+        let result = self.write(path);
+        self.engines.get_mut(self.engine).restore(&current);
+        result
+    }
+
+    /// Saves the current engine's board, in the format given by the
+    /// extension.
+    fn write(&self, path: &Path) -> io::Result<()> {
+        let automaton = self.engines.get(self.engine);
+        let bytes = match Format::from_path(path) {
+            Some(Format::Rle) => self.snapshot().to_rle().into_bytes(),
+            Some(Format::Pbm) => export::pbm(automaton),
+            Some(Format::Pgm) => export::pgm(automaton),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "expected a .rle, .pbm or .pgm file",
+                ))
+            }
+        };
+        std::fs::write(path, bytes)
+    }
+
+    /// Steps the current engine and emits the kernel. While sweeping, the
+    /// direction turns round at either end of the sweep.
     pub fn advance(&mut self) {
-        self.step();
-        self.build_ir();
+        if self.sweep > 0 {
+            match self.generation() {
+                0 => self.backwards = false,
+                generation if generation >= self.sweep => self.backwards = true,
+                _ => {}
+            }
+        }
 
-        match self
-            .fft
-            .process_with_scratch(&mut self.real_buff, &mut self.comp_buff, &mut [])
-        {
-            Ok(_) => {}
-            Err(_) => nih_log!("error with game fft"),
+        match self.backwards {
+            true => {
+                let generation = self.generation();
+                self.step_back();
+                // turn round early rather than stick at the oldest
+                // generation left in the history
+                if self.generation() == generation {
+                    self.backwards = false;
+                }
+            }
+            false => {
+                self.step();
+                self.emit();
+            }
+        }
+    }
+
+    /// Reports whether the current engine has settled, and applies the
+    /// remedy if it has. Sweeps are left alone, since a remedy would stop
+    /// them retracing the generations they came through.
+    fn watch(&mut self) {
+        let status = self.detector.observe(self.engines.get(self.engine));
+        *self.status.write().unwrap() = status;
+        if !status.is_stagnant() || self.remedy == Remedy::Nothing || self.sweep > 0 {
+            return;
+        }
+
+        nih_log!("{status}, applying {:?}", self.remedy);
+        let automaton = self.engines.get_mut(self.engine);
+        // the 1d engine can only be changed on its current row
+        let (width, height) = match self.engine {
+            Engine::Elementary => (automaton.width(), 1),
+            _ => (automaton.width(), automaton.height()),
+        };
+
+        match self.remedy {
+            Remedy::Nothing => {}
+            Remedy::Reseed => automaton.load(&self.seeder.cells(self.size, &mut self.rng)),
+            Remedy::Noise => {
+                let cells: Vec<_> = square(0, 0, width, height)
+                    .filter(|_| self.rng.gen::<f32>() < self.noise)
+                    .collect();
+                automaton.flip(&cells);
+            }
+            Remedy::Perturb => {
+                let side = (self.size / 4).max(1);
+                let (w, h) = (side.min(width), side.min(height));
+                let x = self.rng.gen_range(0..=(width - w) as u32) as i64;
+                let y = self.rng.gen_range(0..=(height - h) as u32) as i64;
+                let cells: Vec<_> = square(x, y, w, h).filter(|_| self.rng.gen()).collect();
+                automaton.flip(&cells);
+            }
         }
 
+        self.detector.clear();
+    }
+
+    fn emit(&mut self) {
+        self.mappers.age.update(self.engines.get(self.engine));
+        self.build_kernel();
+        self.measure();
+        self.write_kernel();
+    }
+
+    /// Sends the kernel's bins to the audio thread. The chunk comes in two
+    /// slices when it wraps round the end of the ring buffer.
+    fn write_kernel(&mut self) {
         match self.prod.write_chunk(self.comp_buff.len()) {
             Ok(mut p) => {
                 let (s1, s2) = p.as_mut_slices();
@@ -88,7 +719,7 @@ impl GOL {
                 let len2 = s2.len();
 
                 s1.copy_from_slice(&self.comp_buff[0..len1]);
-                s2.copy_from_slice(&self.comp_buff[0..len2]);
+                s2.copy_from_slice(&self.comp_buff[len1..len1 + len2]);
 
                 p.commit_all();
             }
@@ -96,118 +727,240 @@ impl GOL {
         }
     }
 
-    fn build_random(&mut self) {
-        self.current_board.clear();
+    fn step(&mut self) {
+        self.engines.get_mut(self.engine).step();
+        self.generation.fetch_add(1, Ordering::Relaxed);
+        self.watch();
+        self.record();
+    }
+
+    /// Publishes the statistics of the generation being emitted.
+    fn measure(&mut self) {
+        let automaton = self.engines.get(self.engine);
+        self.stats.store(&Stats::measure(&self.previous, automaton));
+        self.previous.clear();
+        self.previous.extend_from_slice(automaton.cells());
+    }
 
-        for i in 0..self.size {
-            for j in 0..self.size {
-                if self.rng.gen() {
-                    self.current_board.insert((i as i32, j as i32));
+    /// Saves the current generation to the history, replacing anything
+    /// recorded from it on.
+    fn record(&mut self) {
+        let state = self.engines.get(self.engine).save();
+        self.history.push(self.generation(), state);
+    }
+
+    /// Starts counting generations again from the current board.
+    fn restart(&mut self) {
+        self.generation.store(0, Ordering::Relaxed);
+        self.backwards = false;
+        self.history.clear();
+        self.detector.clear();
+        self.record();
+    }
+
+    /// Fills `comp_buff` with the current generation's kernel, straight
+    /// from the board in spectral mode or through `build_ir` otherwise.
+    /// Either way the taps go through `process_taps`, and empty boards and
+    /// kernels that can't be normalized are replaced by the fallback.
+    fn build_kernel(&mut self) {
+        self.real_buff.fill(0.0);
+
+        let automaton = self.engines.get(self.engine);
+        let empty = automaton.cells().iter().all(|cell| *cell == 0.0);
+        let result = match empty {
+            true => Err(String::from("the board is empty")),
+            false => {
+                match self.spectral.is_some() {
+                    true => self.build_spectral_ir(),
+                    false => self.build_ir(),
                 }
+                self.process_taps().map_err(|e| e.to_string())
             }
+        };
+        self.fall_back(result);
+
+        match self
+            .fft
+            .process_with_scratch(&mut self.real_buff, &mut self.comp_buff, &mut [])
+        {
+            Ok(_) => {}
+            Err(_) => nih_log!("error with game fft"),
         }
     }
 
-    fn step(&mut self) {
-        let mut born = vec![];
-        let mut dying = vec![];
-
-        for cell in self.current_board.iter() {
-            let neighbors = self.find_neighbors(&cell);
-
-            let mut living_neighbors: u8 = 0;
-            for neighbor in neighbors.iter() {
-                if self.current_board.contains(neighbor) {
-                    living_neighbors += 1;
-                } else if !born.contains(neighbor) {
-                    let neighbors_neighbors = self.find_neighbors(neighbor);
-                    let mut neighbors_living_neighbors: u8 = 0;
-                    for neighbor_neighbor in neighbors_neighbors.iter() {
-                        if self.current_board.contains(neighbor_neighbor) {
-                            neighbors_living_neighbors += 1;
-                        }
-                    }
-                    if neighbors_living_neighbors == 3 && !born.contains(neighbor) {
-                        born.push(*neighbor);
-                    }
-                }
-            }
-            if living_neighbors > 3 || living_neighbors < 2 && !dying.contains(cell) {
-                dying.push(*cell);
+    /// Fills `real_buff` with the taps of the spectral kernel.
+    fn build_spectral_ir(&mut self) {
+        let Some(spectral) = &self.spectral else {
+            return;
+        };
+
+        let mut bins = vec![(0.0, 0.0); self.comp_buff.len()];
+        spectral.map(
+            self.engines.get(self.engine),
+            self.sample_rate,
+            self.fft.len(),
+            &mut bins,
+        );
+        for (bin, (magnitude, phase)) in self.comp_buff.iter_mut().zip(bins) {
+            *bin = Complex::from_polar(magnitude, phase);
+        }
+
+        // the inverse fft only takes real dc and nyquist bins
+        self.comp_buff[0].im = 0.0;
+        if self.fft.len() % 2 == 0 {
+            if let Some(nyquist) = self.comp_buff.last_mut() {
+                nyquist.im = 0.0;
             }
         }
-        for cell in dying.iter() {
-            self.current_board.remove(cell);
+
+        match self
+            .ifft
+            .process_with_scratch(&mut self.comp_buff, &mut self.real_buff, &mut [])
+        {
+            Ok(_) => {}
+            Err(_) => nih_log!("error with game ifft"),
+        }
+
+        // the impulse response runs the whole length of the fft and would
+        // wrap round in the overlap-add, so it is cut down to the kernel's
+        // taps, which the delay puts it in the middle of
+        let scale = 1.0 / self.fft.len() as f32;
+        for (i, tap) in self.real_buff.iter_mut().enumerate() {
+            *tap = match i < self.size {
+                true => *tap * scale * Window::Hann.at(i, self.size),
+                false => 0.0,
+            };
         }
-        for cell in born.iter() {
-            self.current_board.insert(*cell);
+    }
+
+    /// Fills `real_buff` with the mapped taps.
+    fn build_ir(&mut self) {
+        let automaton = self.engines.get(self.engine);
+        if self.engine == Engine::Elementary && self.line_mapping == LineMapping::Row {
+            // the top row of the spacetime diagram is the current generation
+            let row = &automaton.cells()[0..automaton.width()];
+            for (tap, cell) in self.real_buff.iter_mut().zip(row) {
+                *tap = 2.0 * cell - 1.0;
+            }
+        } else {
+            self.mappers
+                .get_mut(self.mapping)
+                .map(automaton, &mut self.real_buff[..self.size]);
         }
-        dying.clear();
-        born.clear();
     }
 
-    fn find_neighbors(&self, pos: &(i32, i32)) -> [(i32, i32); 8] {
-        let mut neighbors = [(0, 0); 8];
-        let mut i = 0;
-        for x in -1..2 {
-            for y in -1..2 {
-                if x != 0 || y != 0 {
-                    let true_x = match () {
-                        _ if pos.0 + x > self.size as i32 => x,
-                        _ if pos.0 + x < 0 => self.size as i32 - x,
-                        _ => pos.0 + x,
-                    };
-                    let true_y = match () {
-                        _ if pos.1 + y > self.size as i32 => y,
-                        _ if pos.1 + y < 0 => self.size as i32 - y,
-                        _ => pos.1 + y,
-                    };
+    /// Runs the taps in `real_buff` through the post chain and
+    /// normalization.
+    fn process_taps(&mut self) -> Result<(), Degenerate> {
+        let taps = &mut self.real_buff[..self.size];
+        self.post.apply(taps);
+        self.normalization.apply(taps)?;
+        self.post.finish(taps);
+        Ok(())
+    }
 
-                    neighbors[i] = (true_x, true_y);
-                    i += 1;
+    /// Keeps the taps if they built cleanly, and replaces them with the
+    /// fallback if not. Only logs when kernels start or stop failing, not
+    /// for every generation in between.
+    fn fall_back(&mut self, result: Result<(), String>) {
+        let taps = &mut self.real_buff[..self.size];
+        match result {
+            Ok(()) => {
+                if self.degenerate {
+                    nih_log!("kernels are building cleanly again");
+                    self.degenerate = false;
+                }
+                self.last_taps.clear();
+                self.last_taps.extend_from_slice(taps);
+            }
+            Err(e) => {
+                if !self.degenerate {
+                    nih_log!("{e}, falling back to {:?}", self.fallback);
+                    self.degenerate = true;
+                }
+                match self.fallback {
+                    Fallback::Previous if !self.last_taps.is_empty() => {
+                        taps.copy_from_slice(&self.last_taps)
+                    }
+                    // an impulse in the middle lines up with the dry signal
+                    _ => {
+                        taps.fill(0.0);
+                        taps[self.size / 2] = 1.0;
+                    }
                 }
             }
         }
-
-        neighbors
     }
+}
 
-    fn build_ir(&mut self) {
-        self.real_buff.fill(0.0);
+/// The cells of a `width` by `height` rectangle with its top left corner at
+/// `(x, y)`.
+fn square(x: i64, y: i64, width: usize, height: usize) -> impl Iterator<Item = (i64, i64)> {
+    (0..height as i64).flat_map(move |dy| (0..width as i64).map(move |dx| (x + dx, y + dy)))
+}
 
-        for i in 0..self.size {
-            self.real_buff[i] = {
-                let mut out = 0.0;
-                for j in 0..self.size {
-                    let b_ij = match (
-                        self.current_board.contains(&(i as i32, j as i32)),
-                        i % 2 == 0,
-                    ) {
-                        (true, true) => 1.0,
-                        (true, false) => -1.0,
-                        _ => 0.0,
-                    };
-                    let b_ji = match (
-                        self.current_board.contains(&(j as i32, i as i32)),
-                        i % 2 == 0,
-                    ) {
-                        (true, true) => 1.0,
-                        (true, false) => -1.0,
-                        _ => 0.0,
-                    };
-
-                    out += b_ij + b_ji;
-                }
+/// Remedies draw from their own stream, so they don't repeat the soups the
+/// seed parameters make.
+fn remedy_rng(seed: u64) -> SeedRng {
+    let mut rng = seeder::rng(seed);
+    rng.jump();
+    rng
+}
 
-                out /= self.size as f32;
-                out
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rtrb::RingBuffer;
+
+    #[test]
+    fn kernels_survive_wrapping_the_ring_buffer() {
+        let bins = 96 / 2 + 1;
+        // room for two and a half kernels, so the third wraps round
+        let (prod, mut cons) = RingBuffer::new(bins * 5 / 2);
+        let mut gol = GOL::new(
+            prod,
+            33,
+            96,
+            1,
+            Arc::new(RwLock::new(Status::Running)),
+            Arc::new(AtomicU64::new(0)),
+            Arc::new(Published::default()),
+        );
+
+        for generation in 0..4 {
+            if generation > 0 {
+                gol.advance();
             }
+            let chunk = cons.read_chunk(bins).unwrap();
+            let (first, second) = chunk.as_slices();
+            let kernel: Vec<_> = first.iter().chain(second).copied().collect();
+            chunk.commit_all();
+            assert_eq!(kernel, gol.comp_buff, "generation {generation}");
         }
+    }
 
-        let filter_normalization_factor = self.real_buff.iter().sum::<f32>().recip();
-
-        for sample in &mut self.real_buff {
-            *sample *= filter_normalization_factor;
-        }
+    #[test]
+    fn spectral_kernels_are_normalized() {
+        let (prod, _cons) = RingBuffer::new(96);
+        let mut gol = GOL::new(
+            prod,
+            33,
+            96,
+            1,
+            Arc::new(RwLock::new(Status::Running)),
+            Arc::new(AtomicU64::new(0)),
+            Arc::new(Published::default()),
+        );
+        // the impulse response is centred on the taps, as in the plugin
+        gol.set_spectral(Some(Spectral {
+            delay: 16.0,
+            ..Spectral::default()
+        }));
+        gol.set_normalization(Normalization::Energy, Fallback::Unity);
+        gol.build_kernel();
+
+        assert!(!gol.degenerate);
+        let energy: f32 = gol.real_buff[..gol.size].iter().map(|tap| tap * tap).sum();
+        assert!((energy - 1.0).abs() < 1e-4, "energy {energy}");
     }
 }
@@ -1,13 +1,32 @@
 pub mod consts;
 pub mod editor;
 pub mod gol;
-pub mod gol_utils;
+pub mod modulation;
+pub mod morph;
 
-use std::sync::{Arc, Mutex};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex, RwLock};
 
 use consts::*;
+use morph::{Morph, Transition};
 
-use gol::GOL;
+use automata_core::cycle::Status;
+use automata_core::elementary::LineRule;
+use automata_core::gray_scott::Regime;
+use automata_core::library::{self, LIBRARY};
+use automata_core::neighborhood::{self, Mask, Neighborhood};
+use automata_core::normalize::Normalization;
+use automata_core::pattern::{Pattern, Rotation};
+use automata_core::post::{Chain, Phase, Window};
+use automata_core::reversible::BlockRule;
+use automata_core::rule::{self, Rule};
+use automata_core::seeder::{Noise, Seeder, Symmetry};
+use automata_core::spectrum::{Axis, Grouping, Spectral};
+use automata_core::stats::{Published, Stats};
+use automata_core::topology::Topology;
+use gol::{Engine, Fallback, KernelMapping, LineMapping, Remedy, GOL, MAX_SWEEP};
+use modulation::Modulation;
 use nih_plug::prelude::*;
 use nih_plug_vizia::ViziaState;
 use realfft::num_complex::Complex;
@@ -23,13 +42,43 @@ struct Automata {
     stft: util::StftHelper,
 
     comp_buff: Vec<Complex<f32>>,
-    game_comp_buff: Vec<Complex<f32>>,
+    kernel: Morph,
 
     cons: Option<Consumer<Complex<f32>>>,
+
+    /// What the game has settled into, shown in the editor.
+    status: Arc<RwLock<Status>>,
+    /// The current engine's generation, shown in the editor.
+    generation: Arc<AtomicU64>,
+    /// The latest generation's statistics, for modulation and the editor.
+    stats: Arc<Published>,
+    /// The last statistics read without waiting on a write.
+    last_stats: Stats,
+
+    sample_rate: f32,
+    /// Samples since the last kernel arrived, which a glide is spread over.
+    since_kernel: f32,
 }
 
 enum Tasks {
     Run(usize),
+    /// Goes to an absolute generation, from the history if it is there.
+    JumpTo(u64),
+    StepBack,
+    /// Steps forward, retracing the history after a `StepBack`.
+    StepForward,
+    LoadPattern(Pattern),
+    /// Adds a pattern to the Life engines without clearing them.
+    StampPattern(Pattern),
+    /// Saves a generation to a .rle, .pbm or .pgm file, the current one if
+    /// none is given.
+    Export(PathBuf, Option<u64>),
+    /// Replaces the current engine's board using the seed parameters.
+    Reseed,
+    /// Restarts every engine from the seed, if it or the seed parameters
+    /// have changed since they were seeded.
+    SetSeed(u64),
+    SetSampleRate(f32),
 }
 
 #[derive(Params)]
@@ -37,10 +86,600 @@ struct AutomataParams {
     #[id = "running"]
     running: BoolParam,
 
+    #[nested(array, group = "modulation")]
+    modulation: [modulation::Slot; modulation::SLOTS],
+
+    #[id = "engine"]
+    engine: EnumParam<Engine>,
+
+    #[id = "kernel-mapping"]
+    kernel_mapping: EnumParam<KernelMapping>,
+
+    /// How each new kernel takes over from the last.
+    #[id = "kernel-transition"]
+    kernel_transition: EnumParam<Transition>,
+
+    /// How long a transition takes, in milliseconds. A glide instead takes
+    /// as long as the last generation took to arrive.
+    #[id = "transition-time"]
+    transition_time: FloatParam,
+
+    #[id = "post-window"]
+    post_window: EnumParam<KernelWindow>,
+
+    #[id = "post-kaiser-beta"]
+    post_kaiser_beta: FloatParam,
+
+    #[id = "post-smoothing"]
+    post_smoothing: BoolParam,
+
+    #[id = "post-smoothing-length"]
+    post_smoothing_length: IntParam,
+
+    #[id = "post-phase"]
+    post_phase: EnumParam<KernelPhase>,
+
+    #[id = "post-remove-dc"]
+    post_remove_dc: BoolParam,
+
+    #[id = "normalization"]
+    normalization: EnumParam<KernelNormalization>,
+
+    /// What replaces kernels that can't be normalized.
+    #[id = "normalization-fallback"]
+    normalization_fallback: EnumParam<Fallback>,
+
+    #[id = "kernel-domain"]
+    kernel_domain: EnumParam<KernelDomain>,
+
+    #[id = "spectral-grouping"]
+    spectral_grouping: EnumParam<SpectralGrouping>,
+
+    /// The lowest note of the scale groupings.
+    #[id = "spectral-root"]
+    spectral_root: FloatParam,
+
+    #[id = "spectral-phase"]
+    spectral_phase: BoolParam,
+
+    #[id = "rule"]
+    rule: EnumParam<RulePreset>,
+
+    #[persist = "custom-rule"]
+    custom_rule: Arc<RwLock<String>>,
+
+    #[id = "topology"]
+    topology: EnumParam<BoardTopology>,
+
+    #[id = "neighborhood"]
+    neighborhood: EnumParam<NeighborhoodShape>,
+
+    #[id = "radius"]
+    radius: IntParam,
+
+    #[persist = "custom-mask"]
+    custom_mask: Arc<RwLock<String>>,
+
+    #[id = "pattern-x"]
+    pattern_x: IntParam,
+
+    #[id = "pattern-y"]
+    pattern_y: IntParam,
+
+    #[id = "pattern-rotation"]
+    pattern_rotation: EnumParam<PatternRotation>,
+
+    #[id = "pattern-mirror"]
+    pattern_mirror: BoolParam,
+
+    /// An index into the built-in pattern library.
+    #[id = "library"]
+    library: IntParam,
+
+    /// Every random board is drawn from this, so it is saved with the
+    /// session rather than exposed as an automatable parameter.
+    #[persist = "seed"]
+    seed: Arc<RwLock<u64>>,
+
+    #[id = "seed-source"]
+    seed_source: EnumParam<SeedSource>,
+
+    #[id = "seed-density"]
+    seed_density: FloatParam,
+
+    #[id = "seed-symmetry"]
+    seed_symmetry: EnumParam<SeedSymmetry>,
+
+    #[id = "seed-noise"]
+    seed_noise: EnumParam<SeedNoise>,
+
+    #[id = "seed-clump-size"]
+    seed_clump_size: FloatParam,
+
+    /// The side of the soup in the middle of the board, 0 for all of it.
+    #[id = "seed-soup-size"]
+    seed_soup_size: IntParam,
+
+    #[id = "stagnation-remedy"]
+    stagnation_remedy: EnumParam<Remedy>,
+
+    #[id = "stagnation-noise"]
+    stagnation_noise: FloatParam,
+
+    #[id = "block-rule"]
+    block_rule: EnumParam<BlockRulePreset>,
+
+    /// How far palindromic sweeps run before turning back, 0 for off.
+    #[id = "palindrome-length"]
+    palindrome_length: IntParam,
+
+    #[id = "lenia-radius"]
+    lenia_radius: FloatParam,
+
+    #[id = "lenia-mu"]
+    lenia_mu: FloatParam,
+
+    #[id = "lenia-sigma"]
+    lenia_sigma: FloatParam,
+
+    #[id = "lenia-dt"]
+    lenia_dt: FloatParam,
+
+    #[id = "gs-regime"]
+    gs_regime: EnumParam<GrayScottRegime>,
+
+    #[id = "gs-feed"]
+    gs_feed: FloatParam,
+
+    #[id = "gs-kill"]
+    gs_kill: FloatParam,
+
+    #[id = "gs-diffuse-u"]
+    gs_diffuse_u: FloatParam,
+
+    #[id = "gs-diffuse-v"]
+    gs_diffuse_v: FloatParam,
+
+    #[id = "gs-iterations"]
+    gs_iterations: IntParam,
+
+    #[id = "line-rule"]
+    line_rule: EnumParam<LineRuleKind>,
+
+    #[id = "line-elementary"]
+    line_elementary: IntParam,
+
+    #[id = "line-colors"]
+    line_colors: IntParam,
+
+    #[id = "line-code"]
+    line_code: IntParam,
+
+    #[id = "line-mapping"]
+    line_mapping: EnumParam<LineMapping>,
+
     #[persist = "editor-state"]
     editor_state: Arc<ViziaState>,
 }
 
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+enum RulePreset {
+    #[name = "Conway (B3/S23)"]
+    Conway,
+    #[name = "HighLife (B36/S23)"]
+    HighLife,
+    #[name = "Seeds (B2/S)"]
+    Seeds,
+    #[name = "Day & Night (B3678/S34678)"]
+    DayAndNight,
+    #[name = "Maze (B3/S12345)"]
+    Maze,
+    #[name = "Life Without Death (B3/S012345678)"]
+    LifeWithoutDeath,
+    #[name = "Diamoeba (B35678/S5678)"]
+    Diamoeba,
+    #[name = "Morley (B368/S245)"]
+    Morley,
+    #[name = "Anneal (B4678/S35678)"]
+    Anneal,
+    #[name = "Brian's Brain (B2/S/C3)"]
+    BriansBrain,
+    #[name = "Star Wars (B2/S345/C4)"]
+    StarWars,
+    #[name = "Frogs (B34/S12/C3)"]
+    Frogs,
+    #[name = "Sticks (B2/S3456/C6)"]
+    Sticks,
+    Custom,
+}
+
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+enum KernelWindow {
+    #[name = "no window"]
+    None,
+    Hann,
+    Blackman,
+    Kaiser,
+}
+
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+enum KernelPhase {
+    #[name = "phase as mapped"]
+    Unchanged,
+    #[name = "minimum phase"]
+    Minimum,
+    #[name = "linear phase"]
+    Linear,
+}
+
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+enum KernelNormalization {
+    #[name = "DC gain"]
+    DcGain,
+    #[name = "peak"]
+    Peak,
+    #[name = "RMS"]
+    Rms,
+    #[name = "energy"]
+    Energy,
+    #[name = "max magnitude response"]
+    MaxMagnitude,
+}
+
+/// Whether the board becomes an impulse response or a spectrum.
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+enum KernelDomain {
+    #[name = "impulse response"]
+    Time,
+    /// Each row sets the level of a band, the bottom row lowest.
+    #[name = "spectrum from rows"]
+    Rows,
+    /// Each column sets the level of a band, the left column lowest.
+    #[name = "spectrum from columns"]
+    Columns,
+}
+
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+enum SpectralGrouping {
+    #[name = "linear"]
+    Linear,
+    #[name = "log frequency"]
+    Log,
+    #[name = "major scale"]
+    Major,
+    #[name = "minor scale"]
+    Minor,
+    #[name = "pentatonic scale"]
+    Pentatonic,
+    #[name = "chromatic scale"]
+    Chromatic,
+}
+
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+enum BoardTopology {
+    Torus,
+    #[name = "Klein Bottle"]
+    KleinBottle,
+    #[name = "Projective Plane"]
+    ProjectivePlane,
+    #[name = "Dead Edges"]
+    Dead,
+    #[name = "Mirrored Edges"]
+    Mirror,
+}
+
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+enum NeighborhoodShape {
+    Moore,
+    #[name = "von Neumann"]
+    VonNeumann,
+    Hexagonal,
+    Custom,
+}
+
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+enum PatternRotation {
+    #[name = "0°"]
+    None,
+    #[name = "90°"]
+    Quarter,
+    #[name = "180°"]
+    Half,
+    #[name = "270°"]
+    ThreeQuarters,
+}
+
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+enum SeedSource {
+    #[name = "random soup"]
+    Soup,
+    /// The selected library pattern, in the middle of the board.
+    #[name = "library pattern"]
+    Library,
+}
+
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+enum SeedSymmetry {
+    #[name = "none"]
+    None,
+    C2,
+    C4,
+    D4,
+    D8,
+}
+
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+enum SeedNoise {
+    #[name = "white"]
+    White,
+    #[name = "clustered"]
+    Clustered,
+}
+
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+enum BlockRulePreset {
+    Critters,
+    #[name = "billiard ball"]
+    BilliardBall,
+    Tron,
+}
+
+/// Anything but `Custom` overrides the feed and kill parameters.
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+enum GrayScottRegime {
+    Custom,
+    Spots,
+    Stripes,
+    Mitosis,
+    Coral,
+}
+
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+enum LineRuleKind {
+    Elementary,
+    Totalistic,
+}
+
+impl AutomataParams {
+    /// Resolves the selected rule, parsing the custom rulestring if needed.
+    fn rule(&self) -> Result<Rule, rule::RuleError> {
+        Ok(match self.rule.value() {
+            RulePreset::Conway => Rule::CONWAY,
+            RulePreset::HighLife => Rule::HIGHLIFE,
+            RulePreset::Seeds => Rule::SEEDS,
+            RulePreset::DayAndNight => Rule::DAY_AND_NIGHT,
+            RulePreset::Maze => Rule::MAZE,
+            RulePreset::LifeWithoutDeath => Rule::LIFE_WITHOUT_DEATH,
+            RulePreset::Diamoeba => Rule::DIAMOEBA,
+            RulePreset::Morley => Rule::MORLEY,
+            RulePreset::Anneal => Rule::ANNEAL,
+            RulePreset::BriansBrain => Rule::BRIANS_BRAIN,
+            RulePreset::StarWars => Rule::STAR_WARS,
+            RulePreset::Frogs => Rule::FROGS,
+            RulePreset::Sticks => Rule::STICKS,
+            RulePreset::Custom => Rule::parse(&self.custom_rule.read().unwrap())?,
+        })
+    }
+
+    fn topology(&self) -> Topology {
+        match self.topology.value() {
+            BoardTopology::Torus => Topology::Torus,
+            BoardTopology::KleinBottle => Topology::KleinBottle,
+            BoardTopology::ProjectivePlane => Topology::ProjectivePlane,
+            BoardTopology::Dead => Topology::Dead,
+            BoardTopology::Mirror => Topology::Mirror,
+        }
+    }
+
+    fn gray_scott_feed_kill(&self) -> (f32, f32) {
+        match self.gs_regime.value() {
+            GrayScottRegime::Custom => (self.gs_feed.value(), self.gs_kill.value()),
+            GrayScottRegime::Spots => Regime::Spots.feed_kill(),
+            GrayScottRegime::Stripes => Regime::Stripes.feed_kill(),
+            GrayScottRegime::Mitosis => Regime::Mitosis.feed_kill(),
+            GrayScottRegime::Coral => Regime::Coral.feed_kill(),
+        }
+    }
+
+    fn line_rule(&self) -> LineRule {
+        match self.line_rule.value() {
+            LineRuleKind::Elementary => LineRule::Elementary(self.line_elementary.value() as u8),
+            LineRuleKind::Totalistic => {
+                let colors = self.line_colors.value() as u8;
+                // a radius one rule has 3 * (colors - 1) + 1 possible sums
+                let codes = (colors as u64).pow(3 * (colors as u32 - 1) + 1);
+                LineRule::Totalistic {
+                    colors,
+                    code: self.line_code.value() as u64 % codes,
+                }
+            }
+        }
+    }
+
+    fn neighborhood(&self) -> Result<Neighborhood, neighborhood::MaskError> {
+        let radius = self.radius.value() as u8;
+        let neighborhood = match self.neighborhood.value() {
+            NeighborhoodShape::Moore => Neighborhood::Moore(radius),
+            NeighborhoodShape::VonNeumann => Neighborhood::VonNeumann(radius),
+            NeighborhoodShape::Hexagonal => Neighborhood::Hexagonal,
+            NeighborhoodShape::Custom => {
+                Neighborhood::Custom(Mask::parse(&self.custom_mask.read().unwrap())?)
+            }
+        };
+        neighborhood.check()?;
+        Ok(neighborhood)
+    }
+
+    /// Where loaded and stamped patterns go: the middle of the pattern
+    /// lands on the middle of the board moved by the x and y offsets.
+    fn pattern_placement(&self) -> ((i64, i64), Rotation, bool) {
+        let rotation = match self.pattern_rotation.value() {
+            PatternRotation::None => Rotation::None,
+            PatternRotation::Quarter => Rotation::Quarter,
+            PatternRotation::Half => Rotation::Half,
+            PatternRotation::ThreeQuarters => Rotation::ThreeQuarters,
+        };
+        let middle = FILTER_WINDOW_SIZE as i64 / 2;
+        let centre = (
+            middle + self.pattern_x.value() as i64,
+            middle + self.pattern_y.value() as i64,
+        );
+        (centre, rotation, self.pattern_mirror.value())
+    }
+
+    fn library_entry(&self) -> &'static library::Entry {
+        &LIBRARY[self.library.value() as usize]
+    }
+
+    fn seeder(&self) -> Seeder {
+        let symmetry = match self.seed_symmetry.value() {
+            SeedSymmetry::None => Symmetry::None,
+            SeedSymmetry::C2 => Symmetry::C2,
+            SeedSymmetry::C4 => Symmetry::C4,
+            SeedSymmetry::D4 => Symmetry::D4,
+            SeedSymmetry::D8 => Symmetry::D8,
+        };
+        let noise = match self.seed_noise.value() {
+            SeedNoise::White => Noise::White,
+            SeedNoise::Clustered => Noise::Perlin {
+                scale: self.seed_clump_size.value(),
+            },
+        };
+        let pattern = match self.seed_source.value() {
+            SeedSource::Soup => None,
+            SeedSource::Library => Some(self.library_entry().pattern()),
+        };
+
+        Seeder {
+            density: self.seed_density.value(),
+            symmetry,
+            noise,
+            soup: self.seed_soup_size.value() as usize,
+            pattern,
+        }
+    }
+
+    fn post_chain(&self) -> Chain {
+        let window = match self.post_window.value() {
+            KernelWindow::None => None,
+            KernelWindow::Hann => Some(Window::Hann),
+            KernelWindow::Blackman => Some(Window::Blackman),
+            KernelWindow::Kaiser => Some(Window::Kaiser {
+                beta: self.post_kaiser_beta.value(),
+            }),
+        };
+        // SMOOVE averages over the whole filter, so shorter averages take
+        // the start of it, scaled back up to unity gain
+        let smoothing = match self.post_smoothing.value() {
+            true => {
+                let length = self.post_smoothing_length.value() as usize;
+                SMOOVE[..length]
+                    .iter()
+                    .map(|tap| tap * FILTER_WINDOW_SIZE as f32 / length as f32)
+                    .collect()
+            }
+            false => vec![],
+        };
+        let phase = match self.post_phase.value() {
+            KernelPhase::Unchanged => None,
+            KernelPhase::Minimum => Some(Phase::Minimum),
+            KernelPhase::Linear => Some(Phase::Linear),
+        };
+
+        Chain {
+            window,
+            smoothing,
+            phase,
+            remove_dc: self.post_remove_dc.value(),
+        }
+    }
+
+    fn normalization(&self) -> Normalization {
+        match self.normalization.value() {
+            KernelNormalization::DcGain => Normalization::DcGain,
+            KernelNormalization::Peak => Normalization::Peak,
+            KernelNormalization::Rms => Normalization::Rms,
+            KernelNormalization::Energy => Normalization::Energy,
+            KernelNormalization::MaxMagnitude => Normalization::MaxMagnitude,
+        }
+    }
+
+    fn spectral(&self) -> Option<Spectral> {
+        let axis = match self.kernel_domain.value() {
+            KernelDomain::Time => return None,
+            KernelDomain::Rows => Axis::Rows,
+            KernelDomain::Columns => Axis::Columns,
+        };
+        let scale = |steps: &[u8]| Grouping::Scale {
+            root: self.spectral_root.value(),
+            steps: steps.to_vec(),
+        };
+        let grouping = match self.spectral_grouping.value() {
+            SpectralGrouping::Linear => Grouping::Linear,
+            SpectralGrouping::Log => Grouping::Log,
+            SpectralGrouping::Major => scale(&Grouping::MAJOR),
+            SpectralGrouping::Minor => scale(&Grouping::MINOR),
+            SpectralGrouping::Pentatonic => scale(&Grouping::PENTATONIC),
+            SpectralGrouping::Chromatic => scale(&Grouping::CHROMATIC),
+        };
+
+        Some(Spectral {
+            axis,
+            grouping,
+            phase: self.spectral_phase.value(),
+            delay: KERNEL_DELAY as f32,
+        })
+    }
+
+    fn block_rule(&self) -> BlockRule {
+        match self.block_rule.value() {
+            BlockRulePreset::Critters => BlockRule::CRITTERS,
+            BlockRulePreset::BilliardBall => BlockRule::BILLIARD_BALL,
+            BlockRulePreset::Tron => BlockRule::TRON,
+        }
+    }
+
+    /// Pushes the current parameter values into the game.
+    fn apply(&self, gol: &mut GOL) {
+        gol.set_engine(self.engine.value());
+        gol.set_mapping(self.kernel_mapping.value());
+        gol.set_post(self.post_chain());
+        gol.set_normalization(self.normalization(), self.normalization_fallback.value());
+        gol.set_spectral(self.spectral());
+        gol.set_lenia(
+            self.lenia_radius.value(),
+            self.lenia_mu.value(),
+            self.lenia_sigma.value(),
+            self.lenia_dt.value(),
+        );
+        let (feed, kill) = self.gray_scott_feed_kill();
+        gol.set_gray_scott(
+            feed,
+            kill,
+            self.gs_diffuse_u.value(),
+            self.gs_diffuse_v.value(),
+        );
+        gol.set_gray_scott_iterations(self.gs_iterations.value() as usize);
+        gol.set_line_rule(self.line_rule(), self.line_mapping.value());
+        match self.rule() {
+            Ok(rule) => gol.set_rule(rule),
+            Err(e) => nih_log!("invalid rule: {e}"),
+        }
+        gol.set_topology(self.topology());
+        match self.neighborhood() {
+            Ok(neighborhood) => gol.set_neighborhood(neighborhood),
+            Err(e) => nih_log!("invalid neighborhood: {e}"),
+        }
+        gol.set_seeder(self.seeder());
+        gol.set_remedy(
+            self.stagnation_remedy.value(),
+            self.stagnation_noise.value(),
+        );
+        gol.set_block_rule(self.block_rule());
+        gol.set_sweep(self.palindrome_length.value() as u64);
+    }
+}
+
 impl Default for Automata {
     fn default() -> Self {
         let mut planner = RealFftPlanner::new();
@@ -48,7 +687,7 @@ impl Default for Automata {
         let ifft = planner.plan_fft_inverse(FFT_WINDOW_SIZE);
 
         let comp_buff = ifft.make_input_vec();
-        let game_comp_buff = fft.make_output_vec();
+        let kernel = Morph::new(fft.make_output_vec().len());
 
         Self {
             params: Arc::new(AutomataParams::default()),
@@ -59,9 +698,17 @@ impl Default for Automata {
             stft: util::StftHelper::new(2, WINDOW_SIZE, FFT_WINDOW_SIZE - WINDOW_SIZE),
 
             comp_buff,
-            game_comp_buff,
+            kernel,
 
             cons: None,
+
+            status: Arc::new(RwLock::new(Status::Running)),
+            generation: Arc::new(AtomicU64::new(0)),
+            stats: Arc::new(Published::default()),
+            last_stats: Stats::default(),
+
+            sample_rate: 44100.0,
+            since_kernel: 0.0,
         }
     }
 }
@@ -71,6 +718,219 @@ impl Default for AutomataParams {
         Self {
             editor_state: editor::default_state(),
             running: BoolParam::new("running", false),
+            modulation: std::array::from_fn(|i| modulation::Slot::new(i + 1)),
+            engine: EnumParam::new("engine", Engine::Life),
+            kernel_mapping: EnumParam::new("kernel mapping", KernelMapping::Cross),
+            kernel_transition: EnumParam::new("kernel transition", Transition::Crossfade),
+            transition_time: FloatParam::new(
+                "transition time",
+                50.0,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 2000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+            post_window: EnumParam::new("kernel window", KernelWindow::None),
+            post_kaiser_beta: FloatParam::new(
+                "kaiser beta",
+                6.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 12.0,
+                },
+            ),
+            post_smoothing: BoolParam::new("smoothing", false),
+            post_smoothing_length: IntParam::new(
+                "smoothing length",
+                3,
+                IntRange::Linear {
+                    min: 2,
+                    max: FILTER_WINDOW_SIZE as i32,
+                },
+            ),
+            post_phase: EnumParam::new("kernel phase", KernelPhase::Unchanged),
+            post_remove_dc: BoolParam::new("remove DC", false),
+            normalization: EnumParam::new("normalization", KernelNormalization::DcGain),
+            normalization_fallback: EnumParam::new("degenerate kernels", Fallback::Previous),
+            kernel_domain: EnumParam::new("kernel domain", KernelDomain::Time),
+            spectral_grouping: EnumParam::new("spectral grouping", SpectralGrouping::Log),
+            spectral_root: FloatParam::new(
+                "spectral root",
+                110.0,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 2000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" Hz")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+            spectral_phase: BoolParam::new("spectral phase", false),
+            rule: EnumParam::new("rule", RulePreset::Conway),
+            custom_rule: Arc::new(RwLock::new(Rule::CONWAY.to_string())),
+            topology: EnumParam::new("topology", BoardTopology::Torus),
+            neighborhood: EnumParam::new("neighborhood", NeighborhoodShape::Moore),
+            radius: IntParam::new("radius", 1, IntRange::Linear { min: 1, max: 5 }),
+            custom_mask: Arc::new(RwLock::new(String::from("111/101/111"))),
+            pattern_x: IntParam::new(
+                "pattern x offset",
+                0,
+                IntRange::Linear {
+                    min: -(FILTER_WINDOW_SIZE as i32 / 2),
+                    max: FILTER_WINDOW_SIZE as i32 / 2,
+                },
+            ),
+            pattern_y: IntParam::new(
+                "pattern y offset",
+                0,
+                IntRange::Linear {
+                    min: -(FILTER_WINDOW_SIZE as i32 / 2),
+                    max: FILTER_WINDOW_SIZE as i32 / 2,
+                },
+            ),
+            pattern_rotation: EnumParam::new("pattern rotation", PatternRotation::None),
+            pattern_mirror: BoolParam::new("pattern mirror", false),
+            library: IntParam::new(
+                "library pattern",
+                0,
+                IntRange::Linear {
+                    min: 0,
+                    max: LIBRARY.len() as i32 - 1,
+                },
+            )
+            .with_value_to_string(Arc::new(|index| {
+                let entry = &LIBRARY[index as usize];
+                match (entry.period, entry.velocity) {
+                    (_, Some(velocity)) => format!("{} ({velocity})", entry.name),
+                    (Some(period), None) if period > 1 => format!("{} (p{period})", entry.name),
+                    _ => entry.name.to_string(),
+                }
+            })),
+            seed: Arc::new(RwLock::new(SEED)),
+            seed_source: EnumParam::new("seed source", SeedSource::Soup),
+            seed_density: FloatParam::new(
+                "seed density",
+                0.5,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            seed_symmetry: EnumParam::new("seed symmetry", SeedSymmetry::None),
+            seed_noise: EnumParam::new("seed noise", SeedNoise::White),
+            seed_clump_size: FloatParam::new(
+                "seed clump size",
+                4.0,
+                FloatRange::Linear {
+                    min: 1.0,
+                    max: 16.0,
+                },
+            )
+            .with_step_size(0.5),
+            seed_soup_size: IntParam::new(
+                "seed soup size",
+                0,
+                IntRange::Linear {
+                    min: 0,
+                    max: FILTER_WINDOW_SIZE as i32,
+                },
+            )
+            .with_value_to_string(Arc::new(|size| match size {
+                0 => String::from("whole board"),
+                size => format!("{size}x{size}"),
+            })),
+            stagnation_remedy: EnumParam::new("when stagnant", Remedy::Nothing),
+            stagnation_noise: FloatParam::new(
+                "stagnation noise",
+                0.05,
+                FloatRange::Linear { min: 0.0, max: 0.5 },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            block_rule: EnumParam::new("block rule", BlockRulePreset::Critters),
+            palindrome_length: IntParam::new(
+                "palindrome length",
+                0,
+                IntRange::Linear {
+                    min: 0,
+                    max: MAX_SWEEP as i32,
+                },
+            )
+            .with_value_to_string(Arc::new(|length| match length {
+                0 => String::from("off"),
+                length => format!("{length} generations"),
+            })),
+            lenia_radius: FloatParam::new(
+                "lenia radius",
+                5.0,
+                FloatRange::Linear { min: 2.0, max: 12.0 },
+            )
+            .with_step_size(0.5),
+            lenia_mu: FloatParam::new(
+                "lenia growth center",
+                0.15,
+                FloatRange::Linear { min: 0.05, max: 0.5 },
+            ),
+            lenia_sigma: FloatParam::new(
+                "lenia growth width",
+                0.015,
+                FloatRange::Skewed {
+                    min: 0.002,
+                    max: 0.1,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            ),
+            lenia_dt: FloatParam::new(
+                "lenia dt",
+                0.1,
+                FloatRange::Linear { min: 0.01, max: 1.0 },
+            ),
+            gs_regime: EnumParam::new("gray-scott regime", GrayScottRegime::Spots),
+            gs_feed: FloatParam::new(
+                "gray-scott feed",
+                0.03,
+                FloatRange::Linear { min: 0.0, max: 0.1 },
+            ),
+            gs_kill: FloatParam::new(
+                "gray-scott kill",
+                0.062,
+                FloatRange::Linear { min: 0.03, max: 0.075 },
+            ),
+            gs_diffuse_u: FloatParam::new(
+                "gray-scott u diffusion",
+                1.0,
+                FloatRange::Linear { min: 0.1, max: 1.0 },
+            ),
+            gs_diffuse_v: FloatParam::new(
+                "gray-scott v diffusion",
+                0.5,
+                FloatRange::Linear { min: 0.05, max: 1.0 },
+            ),
+            gs_iterations: IntParam::new(
+                "gray-scott iterations",
+                16,
+                IntRange::Linear { min: 1, max: 64 },
+            ),
+            line_rule: EnumParam::new("1d rule type", LineRuleKind::Elementary),
+            line_elementary: IntParam::new(
+                "1d elementary rule",
+                30,
+                IntRange::Linear { min: 0, max: 255 },
+            ),
+            line_colors: IntParam::new("1d colors", 3, IntRange::Linear { min: 2, max: 4 }),
+            line_code: IntParam::new(
+                "1d totalistic code",
+                1599,
+                IntRange::Linear {
+                    min: 0,
+                    max: 4i32.pow(10) - 1,
+                },
+            ),
+            line_mapping: EnumParam::new("1d mapping", LineMapping::Row),
         }
     }
 }
@@ -111,17 +971,48 @@ impl Plugin for Automata {
     type BackgroundTask = Tasks;
 
     fn task_executor(&mut self) -> TaskExecutor<Self> {
-        let (prod, cons) = RingBuffer::<Complex<f32>>::new(self.game_comp_buff.len() * 1000);
-        let gol = GOL::new(prod, FILTER_WINDOW_SIZE, FFT_WINDOW_SIZE, SEED);
+        let (prod, cons) = RingBuffer::<Complex<f32>>::new(self.kernel.bins() * 1000);
+        let seed = *self.params.seed.read().unwrap();
+        let gol = GOL::new(
+            prod,
+            FILTER_WINDOW_SIZE,
+            FFT_WINDOW_SIZE,
+            seed,
+            self.status.clone(),
+            self.generation.clone(),
+            self.stats.clone(),
+        );
         let protec = Arc::new(Mutex::new(gol));
+        let params = self.params.clone();
 
         self.cons = Some(cons);
 
-        Box::new(move |task: Tasks| match task {
-            Tasks::Run(x) => match protec.try_lock() {
-                Ok(mut gol_lock) => gol_lock.start(x),
-                Err(_) => nih_log!("error taking lock"),
-            },
+        Box::new(move |task: Tasks| match protec.try_lock() {
+            Ok(mut gol_lock) => {
+                params.apply(&mut gol_lock);
+                match task {
+                    Tasks::Run(x) => gol_lock.start(x),
+                    Tasks::JumpTo(generation) => gol_lock.jump_to(generation),
+                    Tasks::StepBack => gol_lock.step_back(),
+                    Tasks::StepForward => gol_lock.step_forward(),
+                    Tasks::LoadPattern(pattern) => {
+                        let (centre, rotation, mirror) = params.pattern_placement();
+                        gol_lock.load_pattern(&pattern, centre, rotation, mirror)
+                    }
+                    Tasks::StampPattern(pattern) => {
+                        let (centre, rotation, mirror) = params.pattern_placement();
+                        gol_lock.stamp(&pattern, centre, rotation, mirror)
+                    }
+                    Tasks::Export(path, generation) => match gol_lock.export(&path, generation) {
+                        Ok(()) => nih_log!("exported to {}", path.display()),
+                        Err(e) => nih_log!("couldn't export to {}: {e}", path.display()),
+                    },
+                    Tasks::Reseed => gol_lock.reseed(),
+                    Tasks::SetSeed(seed) => gol_lock.set_seed(seed),
+                    Tasks::SetSampleRate(sample_rate) => gol_lock.set_sample_rate(sample_rate),
+                }
+            }
+            Err(_) => nih_log!("error taking lock"),
         })
     }
 
@@ -134,6 +1025,9 @@ impl Plugin for Automata {
             self.params.clone(),
             self.params.editor_state.clone(),
             async_executor.clone(),
+            self.status.clone(),
+            self.generation.clone(),
+            self.stats.clone(),
         );
         e
     }
@@ -141,9 +1035,14 @@ impl Plugin for Automata {
     fn initialize(
         &mut self,
         _audio_io_layout: &AudioIOLayout,
-        _buffer_config: &BufferConfig,
+        buffer_config: &BufferConfig,
         context: &mut impl InitContext<Self>,
     ) -> bool {
+        self.sample_rate = buffer_config.sample_rate;
+        context.execute(Tasks::SetSampleRate(buffer_config.sample_rate));
+        // the board is seeded before a saved session's parameters arrive
+        context.execute(Tasks::SetSeed(*self.params.seed.read().unwrap()));
+
         // Resize buffers and perform other potentially expensive initialization operations here.
         // The `reset()` function is always called right after this function. You can remove this
         // function if you do not need it.
@@ -157,6 +1056,8 @@ impl Plugin for Automata {
         // allocate. You can remove this function if you do not need it.
 
         self.stft.set_block_size(WINDOW_SIZE);
+        self.since_kernel = 0.0;
+        self.kernel.settle();
     }
 
     fn process(
@@ -165,29 +1066,44 @@ impl Plugin for Automata {
         _aux: &mut AuxiliaryBuffers,
         _context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
+        // a write in progress leaves the last generation's statistics
+        if let Some(stats) = self.stats.try_load() {
+            self.last_stats = stats;
+        }
+        let modulation = Modulation::new(&self.params.modulation, &self.last_stats);
+        let transition = self.params.kernel_transition.value();
+        let samples = match transition {
+            Transition::Glide => self.since_kernel,
+            _ => {
+                let time = modulation.transition_time(self.params.transition_time.value());
+                time / 1000.0 * self.sample_rate
+            }
+        };
+        let hops = (samples / WINDOW_SIZE as f32).round() as usize;
+
         match self
             .cons
             .as_mut()
             .expect("initialized in task executor func")
-            .read_chunk(self.game_comp_buff.len())
+            .read_chunk(self.kernel.bins())
         {
             Ok(c) => {
                 let (s1, s2) = c.as_slices();
-                let len1 = s1.len();
-                let len2 = s2.len();
-
-                self.game_comp_buff.fill(Complex { re: 0.0, im: 0.0 });
-
-                self.game_comp_buff[0..len1].copy_from_slice(s1);
-                self.game_comp_buff[len1..len1 + len2].copy_from_slice(s2);
-
+                self.kernel.retarget(s1, s2, transition, hops);
                 c.commit_all();
+                self.since_kernel = 0.0;
             }
             Err(_) => {}
         }
+        self.since_kernel += buffer.samples() as f32;
 
         self.stft
-            .process_overlap_add(buffer, 1, |_channel, real_buff| {
+            .process_overlap_add(buffer, 1, |channel, real_buff| {
+                // every channel is filtered with the same kernel
+                if channel == 0 {
+                    self.kernel.advance();
+                }
+
                 match self
                     .fft
                     .process_with_scratch(real_buff, &mut self.comp_buff, &mut [])
@@ -199,7 +1115,7 @@ impl Plugin for Automata {
                     }
                 };
 
-                for (fft_bin, kernel_bin) in self.comp_buff.iter_mut().zip(&self.game_comp_buff) {
+                for (fft_bin, kernel_bin) in self.comp_buff.iter_mut().zip(self.kernel.current()) {
                     *fft_bin *= *kernel_bin * GAIN_COMP;
                 }
 
@@ -0,0 +1,137 @@
+use automata_core::stats::Stats;
+use nih_plug::prelude::*;
+
+/// How many source to destination routes the matrix has.
+pub const SLOTS: usize = 4;
+
+/// How far a full modulation moves the transition time, in octaves.
+const TIME_RANGE: f32 = 4.0;
+
+/// A measurement of the board, scaled to `[0, 1]`.
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+pub enum Source {
+    #[name = "off"]
+    Off,
+    /// The share of cells alive.
+    #[name = "population"]
+    Population,
+    /// Births as a share of the larger of this and the last population.
+    #[name = "births"]
+    Births,
+    #[name = "deaths"]
+    Deaths,
+    #[name = "bounding width"]
+    Width,
+    #[name = "bounding height"]
+    Height,
+    #[name = "centroid x"]
+    CentroidX,
+    #[name = "centroid y"]
+    CentroidY,
+    #[name = "entropy"]
+    Entropy,
+    /// The share of cells that changed.
+    #[name = "activity"]
+    Activity,
+}
+
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+pub enum Destination {
+    #[name = "transition time"]
+    TransitionTime,
+}
+
+impl Source {
+    pub fn value(&self, stats: &Stats) -> f32 {
+        let cells = (stats.width * stats.height).max(1) as f32;
+        let before = stats.population + stats.deaths - stats.births;
+        let turnover = stats.population.max(before).max(1) as f32;
+        let extent = |(left, top, right, bottom): (usize, usize, usize, usize)| {
+            (
+                (right - left + 1) as f32 / stats.width.max(1) as f32,
+                (bottom - top + 1) as f32 / stats.height.max(1) as f32,
+            )
+        };
+        // an empty board sits in the middle
+        let (x, y) = stats.centroid.unwrap_or((
+            (stats.width as f32 - 1.0) / 2.0,
+            (stats.height as f32 - 1.0) / 2.0,
+        ));
+
+        match self {
+            Source::Off => 0.0,
+            Source::Population => stats.population as f32 / cells,
+            Source::Births => stats.births as f32 / turnover,
+            Source::Deaths => stats.deaths as f32 / turnover,
+            Source::Width => stats.bounds.map_or(0.0, |bounds| extent(bounds).0),
+            Source::Height => stats.bounds.map_or(0.0, |bounds| extent(bounds).1),
+            Source::CentroidX => x / (stats.width as f32 - 1.0).max(1.0),
+            Source::CentroidY => y / (stats.height as f32 - 1.0).max(1.0),
+            Source::Entropy => stats.entropy,
+            Source::Activity => stats.activity,
+        }
+    }
+}
+
+/// One route of the modulation matrix.
+#[derive(Params)]
+pub struct Slot {
+    #[id = "source"]
+    pub source: EnumParam<Source>,
+
+    #[id = "destination"]
+    pub destination: EnumParam<Destination>,
+
+    /// Scales the source, so a negative amount turns the destination down
+    /// as the source goes up.
+    #[id = "amount"]
+    pub amount: FloatParam,
+}
+
+impl Slot {
+    /// `index` counts from 1, to match the parameter ids.
+    pub fn new(index: usize) -> Self {
+        Self {
+            source: EnumParam::new(format!("mod {index} source"), Source::Off),
+            destination: EnumParam::new(
+                format!("mod {index} destination"),
+                Destination::TransitionTime,
+            ),
+            amount: FloatParam::new(
+                format!("mod {index} amount"),
+                0.0,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
+                },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+        }
+    }
+}
+
+/// The sum of every route into each destination.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Modulation {
+    transition_time: f32,
+}
+
+impl Modulation {
+    pub fn new(slots: &[Slot], stats: &Stats) -> Self {
+        let mut modulation = Self::default();
+        for slot in slots {
+            let amount = slot.amount.value() * slot.source.value().value(stats);
+            match slot.destination.value() {
+                Destination::TransitionTime => modulation.transition_time += amount,
+            }
+        }
+        modulation
+    }
+
+    /// `time` and the result are in milliseconds.
+    pub fn transition_time(&self, time: f32) -> f32 {
+        time * (self.transition_time * TIME_RANGE).exp2()
+    }
+}
@@ -0,0 +1,141 @@
+use std::f32::consts::{PI, TAU};
+
+use nih_plug::prelude::*;
+use realfft::num_complex::Complex;
+
+/// How the kernel gets from one generation's spectrum to the next.
+#[derive(Enum, Debug, PartialEq, Clone, Copy)]
+pub enum Transition {
+    /// Swaps kernels as soon as they arrive.
+    #[name = "instant"]
+    Instant,
+    /// Blends the bins straight across, the same as crossfading the two
+    /// impulse responses.
+    #[name = "crossfade"]
+    Crossfade,
+    /// Moves each bin's magnitude and phase across separately, so bins that
+    /// are out of phase don't dip on the way.
+    #[name = "spectral"]
+    Spectral,
+    /// Like `Spectral`, but over as long as the last generation took to
+    /// arrive, so the kernel never stops moving while generations keep
+    /// coming.
+    #[name = "glide"]
+    Glide,
+}
+
+impl Transition {
+    fn polar(&self) -> bool {
+        matches!(self, Transition::Spectral | Transition::Glide)
+    }
+}
+
+/// The kernel the audio is filtered with, moved towards each new kernel
+/// over a number of STFT hops.
+///
+/// The bins are those of an even length real FFT, so the first and last
+/// are DC and Nyquist, and are kept real.
+///
+/// Everything is allocated up front, so it can all happen on the audio
+/// thread.
+pub struct Morph {
+    from: Vec<Complex<f32>>,
+    to: Vec<Complex<f32>>,
+    current: Vec<Complex<f32>>,
+    hop: usize,
+    hops: usize,
+    polar: bool,
+}
+
+impl Morph {
+    pub fn new(bins: usize) -> Self {
+        Self {
+            from: vec![Complex::default(); bins],
+            to: vec![Complex::default(); bins],
+            current: vec![Complex::default(); bins],
+            hop: 0,
+            hops: 0,
+            polar: false,
+        }
+    }
+
+    pub fn bins(&self) -> usize {
+        self.current.len()
+    }
+
+    /// Starts moving towards the kernel split across `first` and `second`,
+    /// from wherever the current one has got to, arriving after `hops`
+    /// hops. Bins past the end of both are zeroed.
+    pub fn retarget(
+        &mut self,
+        first: &[Complex<f32>],
+        second: &[Complex<f32>],
+        transition: Transition,
+        hops: usize,
+    ) {
+        self.from.copy_from_slice(&self.current);
+        self.to.fill(Complex::default());
+        self.to[..first.len()].copy_from_slice(first);
+        self.to[first.len()..first.len() + second.len()].copy_from_slice(second);
+
+        self.hop = 0;
+        self.hops = match transition {
+            Transition::Instant => 0,
+            _ => hops,
+        };
+        self.polar = transition.polar();
+        if self.hops == 0 {
+            self.settle();
+        }
+    }
+
+    /// Jumps to the kernel being moved towards.
+    pub fn settle(&mut self) {
+        self.current.copy_from_slice(&self.to);
+        self.hop = self.hops;
+    }
+
+    /// Moves on by one hop. Called once a hop, however many channels are
+    /// filtered with the kernel.
+    pub fn advance(&mut self) {
+        if self.hop < self.hops {
+            self.hop += 1;
+            let t = self.hop as f32 / self.hops as f32;
+            let last = self.current.len().saturating_sub(1);
+            let bins = self.current.iter_mut().zip(&self.from).zip(&self.to);
+            for (i, ((current, from), to)) in bins.enumerate() {
+                // the inverse fft only takes real dc and nyquist bins, so
+                // they can't turn through a phase
+                let real = i == 0 || i == last;
+                *current = match self.polar && !real {
+                    true => interpolate_polar(*from, *to, t),
+                    false => *from + (*to - *from) * t,
+                };
+                if real {
+                    current.im = 0.0;
+                }
+            }
+        }
+    }
+
+    /// The kernel to filter the current hop with.
+    pub fn current(&self) -> &[Complex<f32>] {
+        &self.current
+    }
+}
+
+/// `t` of the way from `from` to `to`, taking the shorter way round for
+/// the phase.
+fn interpolate_polar(from: Complex<f32>, to: Complex<f32>, t: f32) -> Complex<f32> {
+    let (from_norm, from_arg) = from.to_polar();
+    let (to_norm, to_arg) = to.to_polar();
+    // silent bins have no phase of their own, so take the other's
+    let (from_arg, to_arg) = match (from_norm > 0.0, to_norm > 0.0) {
+        (false, _) => (to_arg, to_arg),
+        (_, false) => (from_arg, from_arg),
+        _ => (from_arg, to_arg),
+    };
+
+    let turn = (to_arg - from_arg + PI).rem_euclid(TAU) - PI;
+    Complex::from_polar(from_norm + (to_norm - from_norm) * t, from_arg + turn * t)
+}